@@ -1,7 +1,10 @@
 use crate::field::{named::*, Field, COUNT};
-use crate::piece::{ColoredPiece, Side};
+use crate::move_generator;
+use crate::mv::Move;
+use crate::piece::{ColoredPiece, Piece, Side};
+use crate::zobrist;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct Board {
     pub pieces: [ColoredPiece; COUNT],
     pub active: Side,
@@ -9,6 +12,29 @@ pub struct Board {
     pub en_passant: Option<Field>,
     pub halfmove_clock: u32,
     pub full_moves: u32,
+    key: u64,
+}
+
+// The key is derived from the other fields
+impl PartialEq for Board {
+    fn eq(&self, other: &Self) -> bool {
+        self.pieces == other.pieces
+            && self.active == other.active
+            && self.can_castle == other.can_castle
+            && self.en_passant == other.en_passant
+            && self.halfmove_clock == other.halfmove_clock
+            && self.full_moves == other.full_moves
+    }
+}
+
+/// State which cannot be recovered from a move alone, returned by
+/// `Board::make_move` and consumed by `Board::unmake_move`
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Undo {
+    captured: ColoredPiece,
+    can_castle: [bool; 4],
+    en_passant: Option<Field>,
+    halfmove_clock: u32,
 }
 
 impl Board {
@@ -28,14 +54,17 @@ impl Board {
         for (idx, piece) in list {
             pieces[idx.0] = *piece;
         }
-        Board {
+        let mut b = Board {
             pieces,
             active,
             can_castle,
             en_passant,
             halfmove_clock,
             full_moves,
-        }
+            key: 0,
+        };
+        b.update_hash();
+        b
     }
 
     pub fn initial() -> Board {
@@ -76,11 +105,185 @@ impl Board {
         ];
         Board::new(&LIST, Side::White, [true, true, true, true], None, 0, 1)
     }
+
+    /// Zobrist key of the position. It covers pieces, side to move, castling
+    /// rights and the en-passant field when a capture there is legal. Moves
+    /// keep it up to date, changing the fields directly needs `update_hash`.
+    pub fn hash(&self) -> u64 {
+        self.key
+    }
+
+    /// Recomputes the Zobrist key, needed after changing fields directly
+    pub fn update_hash(&mut self) {
+        self.key = zobrist::compute(self);
+    }
+
+    /// Checks whether field `f` is attacked by any piece of side `by`
+    pub fn is_attacked(&self, f: Field, by: Side) -> bool {
+        move_generator::is_attacked(self, f, by)
+    }
+
+    /// Checks whether the king of the side to move is attacked
+    pub fn in_check(&self) -> bool {
+        move_generator::in_check(self, self.active)
+    }
+
+    /// Fields of the pieces giving check to the king of the side to move
+    pub fn checkers(&self) -> Vec<Field> {
+        match move_generator::find_king(self, self.active) {
+            Some(king) => move_generator::attackers(self, king, self.active.opposite()),
+            None => Vec::new(),
+        }
+    }
+
+    /// The side to move is in check and has no legal move
+    pub fn is_checkmate(&self) -> bool {
+        self.in_check() && move_generator::generate_legal(self).is_empty()
+    }
+
+    /// The side to move is not in check and has no legal move. Draw rules
+    /// such as insufficient material or repetition are not considered.
+    pub fn is_stalemate(&self) -> bool {
+        !self.in_check() && move_generator::generate_legal(self).is_empty()
+    }
+
+    /// Applies a pseudo-legal move for the side to move. Panics unless its
+    /// kind matches the board, as for moves from the generator,
+    /// `Move::from_uci` or `Move::from_san`.
+    pub fn make_move(&mut self, m: Move) -> Undo {
+        assert!(self.kind_matches(m), "Wrong kind of move {:?}", m);
+        let mut undo = Undo {
+            captured: ColoredPiece::Empty,
+            can_castle: self.can_castle,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+        };
+        self.key ^= zobrist::state_key(self);
+        self.en_passant = None;
+        self.halfmove_clock += 1;
+        match m {
+            Move::KingCastle(c) | Move::QueenCastle(c) => {
+                // In Chess960 the king or rook may land on the other's field
+                self.put(c.king_from(), ColoredPiece::Empty);
+                self.put(c.rook_from(), ColoredPiece::Empty);
+                self.put(c.king_to(), ColoredPiece::P(Piece::King, self.active));
+                self.put(c.rook_to(), ColoredPiece::P(Piece::Rook, self.active));
+                self.revoke_castling(c.king_from());
+            }
+            _ => {
+                let d = m.move_data().expect("Non-castling move has data");
+                let piece = self.pieces[d.from().0];
+                if let Some(f) = m.captured_square() {
+                    undo.captured = self.pieces[f.0];
+                    self.put(f, ColoredPiece::Empty);
+                }
+                self.put(d.from(), ColoredPiece::Empty);
+                match d.promotion() {
+                    Some(p) => self.put(d.to(), ColoredPiece::P(p, self.active)),
+                    None => self.put(d.to(), piece),
+                };
+                if piece == ColoredPiece::P(Piece::Pawn, self.active) || m.is_capture() {
+                    self.halfmove_clock = 0;
+                }
+                if m.is_double_push() {
+                    let row = (d.from().row() + d.to().row()) / 2;
+                    self.en_passant = Some(Field::new(row, d.from().column()));
+                }
+                self.revoke_castling(d.from());
+                self.revoke_castling(d.to());
+            }
+        }
+        if self.active == Side::Black {
+            self.full_moves += 1;
+        }
+        self.active = self.active.opposite();
+        self.key ^= zobrist::state_key(self);
+        debug_assert_eq!(self.key, zobrist::compute(self));
+        undo
+    }
+
+    /// Reverts a move applied by `make_move`
+    pub fn unmake_move(&mut self, m: Move, undo: Undo) {
+        self.key ^= zobrist::state_key(self);
+        self.active = self.active.opposite();
+        if self.active == Side::Black {
+            self.full_moves -= 1;
+        }
+        match m {
+            Move::KingCastle(c) | Move::QueenCastle(c) => {
+                self.put(c.king_to(), ColoredPiece::Empty);
+                self.put(c.rook_to(), ColoredPiece::Empty);
+                self.put(c.king_from(), ColoredPiece::P(Piece::King, self.active));
+                self.put(c.rook_from(), ColoredPiece::P(Piece::Rook, self.active));
+            }
+            _ => {
+                let d = m.move_data().expect("Non-castling move has data");
+                let piece = match d.promotion() {
+                    Some(_) => ColoredPiece::P(Piece::Pawn, self.active),
+                    None => self.pieces[d.to().0],
+                };
+                self.put(d.to(), ColoredPiece::Empty);
+                self.put(d.from(), piece);
+                if let Some(f) = m.captured_square() {
+                    self.put(f, undo.captured);
+                }
+            }
+        }
+        self.can_castle = undo.can_castle;
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.key ^= zobrist::state_key(self);
+        debug_assert_eq!(self.key, zobrist::compute(self));
+    }
+
+    /// Places `piece` on field `f` keeping the key up to date
+    fn put(&mut self, f: Field, piece: ColoredPiece) {
+        self.key ^= zobrist::piece_key(self.pieces[f.0], f) ^ zobrist::piece_key(piece, f);
+        self.pieces[f.0] = piece;
+    }
+
+    /// Drops castling rights once a king or rook leaves, or a rook is captured on `f`
+    fn revoke_castling(&mut self, f: Field) {
+        match f {
+            E1 => {
+                self.can_castle[0] = false;
+                self.can_castle[1] = false;
+            }
+            H1 => self.can_castle[0] = false,
+            A1 => self.can_castle[1] = false,
+            E8 => {
+                self.can_castle[2] = false;
+                self.can_castle[3] = false;
+            }
+            H8 => self.can_castle[2] = false,
+            A8 => self.can_castle[3] = false,
+            _ => {}
+        }
+    }
+
+    /// Checks that a pawn move two rows forward is a double push, a pawn
+    /// taking on the en-passant field an en-passant capture and any other
+    /// move onto an occupied field a capture
+    fn kind_matches(&self, m: Move) -> bool {
+        let d = match m.move_data() {
+            Some(d) => d,
+            None => return true,
+        };
+        let pawn = matches!(self.pieces[d.from().0], ColoredPiece::P(Piece::Pawn, _));
+        let diagonal = d.from().column() != d.to().column();
+        let double = pawn && (d.from().row() as i32 - d.to().row() as i32).abs() == 2;
+        let en_passant = pawn && diagonal && self.pieces[d.to().0] == ColoredPiece::Empty;
+        let capture = self.pieces[d.to().0] != ColoredPiece::Empty;
+        m.is_double_push() == double
+            && matches!(m, Move::EnPassant(_)) == en_passant
+            && matches!(m, Move::Capture(_) | Move::PromotionCapture(_)) == capture
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fen::{from_fen, to_fen};
     use crate::field::{fields, row};
     use crate::piece::named;
 
@@ -171,4 +374,195 @@ mod tests {
         assert_eq!(board.pieces[H7.0], named::P);
         assert_eq!(board.pieces[H8.0], named::k);
     }
+
+    fn check_move(fen: &str, m: &str, expected: &str) {
+        let mut b = from_fen(fen).unwrap();
+        // Move kinds and castling sides follow from the position
+        let m = move_generator::generate(&b)
+            .into_iter()
+            .find(|g| g.to_string() == m)
+            .unwrap_or_else(|| m.parse::<Move>().unwrap());
+        let undo = b.make_move(m);
+        assert_eq!(to_fen(&b), expected);
+        assert_eq!(b.hash(), from_fen(expected).unwrap().hash());
+        b.unmake_move(m, undo);
+        assert_eq!(to_fen(&b), fen);
+        assert_eq!(b.hash(), from_fen(fen).unwrap().hash());
+    }
+
+    #[test]
+    fn make_move() {
+        check_move(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "e2e4",
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+        );
+        check_move(
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "d7d5",
+            "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+        );
+        check_move(
+            "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+            "g1f3",
+            "rnbqkbnr/ppp1pppp/8/3p4/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2",
+        );
+        check_move(
+            "rnbqkbnr/ppp1pppp/8/3p4/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2",
+            "d5xe4",
+            "rnbqkbnr/ppp1pppp/8/8/4p3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 0 3",
+        );
+    }
+
+    #[test]
+    fn make_move_en_passant() {
+        check_move(
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "e5xf6",
+            "rnbqkbnr/ppp1p1pp/5P2/3p4/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 3",
+        );
+        check_move(
+            "4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1",
+            "d4xe3",
+            "4k3/8/8/8/8/4p3/8/4K3 w - - 0 2",
+        );
+    }
+
+    #[test]
+    fn make_move_promotion() {
+        check_move(
+            "r3k3/1P6/8/8/8/8/8/4K3 w q - 3 40",
+            "b7b8=N",
+            "rN2k3/8/8/8/8/8/8/4K3 b q - 0 40",
+        );
+        check_move(
+            "r3k3/1P6/8/8/8/8/8/4K3 w q - 3 40",
+            "b7xa8=Q",
+            "Q3k3/8/8/8/8/8/8/4K3 b - - 0 40",
+        );
+    }
+
+    #[test]
+    fn make_move_castle() {
+        check_move(
+            "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+            "O-O",
+            "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1",
+        );
+        check_move(
+            "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+            "O-O-O",
+            "r3k2r/8/8/8/8/8/8/2KR3R b kq - 1 1",
+        );
+        check_move(
+            "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+            "O-O",
+            "r4rk1/8/8/8/8/8/8/R3K2R w KQ - 1 2",
+        );
+        check_move(
+            "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+            "O-O-O",
+            "2kr3r/8/8/8/8/8/8/R3K2R w KQ - 1 2",
+        );
+        check_move(
+            "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+            "h1h2",
+            "r3k2r/8/8/8/8/8/7R/R3K3 b Qkq - 1 1",
+        );
+        check_move(
+            "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+            "a1xa8",
+            "R3k2r/8/8/8/8/8/8/4K2R b Kk - 0 1",
+        );
+        check_move(
+            "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+            "e1d1",
+            "r3k2r/8/8/8/8/8/8/R2K3R b kq - 1 1",
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Wrong kind of move")]
+    fn make_move_wrong_kind() {
+        // A double push parsed without the board
+        Board::initial().make_move("e2e4".parse::<Move>().unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "Wrong kind of move")]
+    fn make_move_wrong_kind_en_passant() {
+        let mut b =
+            from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3").unwrap();
+        b.make_move("e5xf6".parse::<Move>().unwrap());
+    }
+
+    #[test]
+    fn check() {
+        let b = Board::initial();
+        assert!(!b.in_check());
+        assert!(b.checkers().is_empty());
+        assert!(!b.is_checkmate() && !b.is_stalemate());
+
+        // Back-rank mate
+        let b = from_fen("3R2k1/5ppp/8/8/8/8/8/6K1 b - - 1 1").unwrap();
+        assert!(b.in_check());
+        assert_eq!(b.checkers(), vec![D8]);
+        assert!(b.is_checkmate() && !b.is_stalemate());
+        let b = from_fen("3R2k1/5pp1/7p/8/8/8/8/6K1 b - - 1 1").unwrap();
+        assert!(b.in_check() && !b.is_checkmate());
+
+        // Smothered mate
+        let b = from_fen("6rk/5Npp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        assert_eq!(b.checkers(), vec![F7]);
+        assert!(b.is_checkmate());
+
+        // Stalemate in the corner
+        let b = from_fen("k7/8/1Q6/8/8/8/8/7K b - - 0 1").unwrap();
+        assert!(!b.in_check());
+        assert!(b.is_stalemate() && !b.is_checkmate());
+
+        // Double check by rook and knight
+        let b = from_fen("4r1k1/8/8/8/8/5n2/8/4K3 w - - 0 1").unwrap();
+        let mut checkers = b.checkers();
+        checkers.sort();
+        assert_eq!(checkers, vec![F3, E8]);
+        assert!(!b.is_checkmate());
+    }
+
+    #[test]
+    fn hash() {
+        let play = |moves: &[&str]| {
+            let mut b = Board::initial();
+            for m in moves {
+                let m = Move::from_uci(m, &b).unwrap();
+                b.make_move(m);
+            }
+            b
+        };
+        let a = play(&["g1f3", "g8f6", "b1c3"]);
+        let b = play(&["b1c3", "g8f6", "g1f3"]);
+        assert_eq!(a.hash(), b.hash());
+        assert_ne!(a.hash(), Board::initial().hash());
+        let d = play(&["g1f3", "g8f6", "f3g1", "f6g8"]);
+        assert_eq!(d.hash(), Board::initial().hash());
+        // Castling rights are part of the key
+        let e = play(&["e2e4", "e7e5", "e1e2", "e8e7", "e2e1", "e7e8"]);
+        assert_eq!(e.pieces, play(&["e2e4", "e7e5"]).pieces);
+        assert_ne!(e.hash(), play(&["e2e4", "e7e5"]).hash());
+
+        // Fields changed directly need an update of the key
+        let mut f = Board::initial();
+        f.active = Side::Black;
+        assert_eq!(
+            f,
+            from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1").unwrap()
+        );
+        f.update_hash();
+        assert_eq!(
+            f.hash(),
+            from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1")
+                .unwrap()
+                .hash()
+        );
+    }
 }
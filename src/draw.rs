@@ -0,0 +1,183 @@
+use crate::board::Board;
+use crate::field::Field;
+use crate::move_generator::generate_legal;
+use crate::piece::{ColoredPiece, Piece, Side};
+use std::collections::HashSet;
+
+impl Board {
+    /// Fifty moves by each side without a capture or pawn move, so either
+    /// player may claim a draw. Checkmate on the last of them still wins.
+    pub fn is_fifty_move_claimable(&self) -> bool {
+        self.halfmove_clock >= 100 && !self.is_checkmate()
+    }
+
+    /// Seventy-five moves by each side without a capture or pawn move end
+    /// the game in a draw, unless the last of them gives checkmate
+    pub fn is_seventy_five_move_draw(&self) -> bool {
+        self.halfmove_clock >= 150 && !self.is_checkmate()
+    }
+
+    /// Neither side can ever mate: kings with at most one knight or bishop,
+    /// or with any number of bishops all on fields of the same color
+    pub fn is_insufficient_material(&self) -> bool {
+        let mut minors = Vec::new();
+        for (idx, piece) in self.pieces.iter().enumerate() {
+            match piece {
+                ColoredPiece::P(Piece::King, _) | ColoredPiece::Empty => {}
+                ColoredPiece::P(Piece::Knight, _) | ColoredPiece::P(Piece::Bishop, _) => {
+                    minors.push((Field(idx), *piece))
+                }
+                _ => return false,
+            }
+        }
+        minors.len() <= 1
+            || minors.iter().all(|(f, piece)| {
+                matches!(piece, ColoredPiece::P(Piece::Bishop, _))
+                    && field_color(*f) == field_color(minors[0].0)
+            })
+    }
+
+    /// Checks whether the pieces of `side` alone are able to mate: a pawn,
+    /// rook or queen, or two minor pieces other than bishops of one color.
+    /// A side without them cannot win on time.
+    pub fn has_mating_material(&self, side: Side) -> bool {
+        let mut knights = 0;
+        let mut bishops = [0; 2];
+        for (idx, piece) in self.pieces.iter().enumerate() {
+            match piece {
+                ColoredPiece::P(p, s) if *s == side => match p {
+                    Piece::King => {}
+                    Piece::Knight => knights += 1,
+                    Piece::Bishop => bishops[field_color(Field(idx))] += 1,
+                    Piece::Pawn | Piece::Rook | Piece::Queen => return true,
+                },
+                _ => {}
+            }
+        }
+        knights >= 2 || knights + bishops[0].min(1) + bishops[1].min(1) >= 2
+    }
+
+    /// Searches all positions reachable without a capture or pawn move for a
+    /// checkmate. The position is dead if there is none and every line runs
+    /// into positions already seen, which happens behind a locked pawn chain.
+    /// Returns false when a capture or pawn move is possible or more than
+    /// `limit` positions would be searched.
+    pub fn is_dead_position(&self, limit: usize) -> bool {
+        if self.is_insufficient_material() {
+            return true;
+        }
+        let mut seen = HashSet::new();
+        seen.insert(self.hash());
+        let mut stack = vec![self.clone()];
+        while let Some(b) = stack.pop() {
+            let moves = generate_legal(&b);
+            if moves.is_empty() && b.in_check() {
+                return false;
+            }
+            for m in moves {
+                let d = m.move_data();
+                let pawn = d.is_some_and(|d| {
+                    b.pieces[d.from().0] == ColoredPiece::P(Piece::Pawn, b.active)
+                });
+                if m.is_capture() || pawn {
+                    return false;
+                }
+                let mut next = b.clone();
+                next.make_move(m);
+                if seen.insert(next.hash()) {
+                    if seen.len() > limit {
+                        return false;
+                    }
+                    stack.push(next);
+                }
+            }
+        }
+        true
+    }
+}
+
+/// 0 for dark and 1 for light fields
+fn field_color(f: Field) -> usize {
+    (f.row() + f.column()) % 2
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fen::from_fen;
+    use crate::mv::Move;
+    use crate::piece::Side;
+
+    #[test]
+    fn fifty_moves() {
+        let mut b = from_fen("4k3/4p3/8/8/8/8/8/4K2R w - - 99 80").unwrap();
+        assert!(!b.is_fifty_move_claimable());
+        b.make_move(Move::from_san("Rh2", &b).unwrap());
+        assert!(b.is_fifty_move_claimable());
+        assert!(!b.is_seventy_five_move_draw());
+        b.make_move(Move::from_san("e6", &b).unwrap());
+        assert!(!b.is_fifty_move_claimable());
+
+        let b = from_fen("4k3/8/8/8/8/8/8/4K2R w - - 150 80").unwrap();
+        assert!(b.is_seventy_five_move_draw());
+        let b = from_fen("k7/8/1K6/8/8/8/8/6Q1 b - - 150 100").unwrap();
+        assert!(b.is_seventy_five_move_draw());
+    }
+
+    #[test]
+    fn insufficient_material() {
+        let insufficient = |fen: &str| from_fen(fen).unwrap().is_insufficient_material();
+        assert!(insufficient("4k3/8/8/8/8/8/8/4K3 w - - 0 1"));
+        assert!(insufficient("4k3/8/8/8/8/8/8/4KN2 w - - 0 1"));
+        assert!(insufficient("4k3/8/8/8/8/8/8/2b1K3 w - - 0 1"));
+        // Bishops on c1, f8 and h6 are all on dark fields
+        assert!(insufficient("4kb2/8/7B/8/8/8/8/2B1K3 w - - 0 1"));
+        assert!(!insufficient("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1"));
+        assert!(!insufficient("4kb2/8/8/8/8/8/8/4KB2 w - - 0 1"));
+        assert!(!insufficient("4k3/8/8/8/8/8/8/4KNN1 w - - 0 1"));
+        assert!(!insufficient("4kn2/8/8/8/8/8/8/4KB2 w - - 0 1"));
+        assert!(!insufficient("4k3/8/8/8/8/8/7P/4K3 w - - 0 1"));
+        assert!(!insufficient(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+        ));
+    }
+
+    #[test]
+    fn mating_material() {
+        let b = from_fen("4k3/8/8/8/8/8/8/3qKN2 w - - 0 1").unwrap();
+        assert!(!b.has_mating_material(Side::White));
+        assert!(b.has_mating_material(Side::Black));
+        let b = from_fen("4k3/8/7B/8/8/8/8/2B1K1N1 w - - 0 1").unwrap();
+        assert!(b.has_mating_material(Side::White));
+        assert!(!b.has_mating_material(Side::Black));
+        let b = from_fen("4k3/8/7B/8/8/8/8/2B1K3 w - - 0 1").unwrap();
+        assert!(!b.has_mating_material(Side::White));
+        let b = from_fen("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1").unwrap();
+        assert!(b.has_mating_material(Side::White));
+        let b = from_fen("4k3/8/8/8/8/8/8/4KNN1 w - - 0 1").unwrap();
+        assert!(b.has_mating_material(Side::White));
+    }
+
+    #[test]
+    fn dead_position() {
+        // Neither king can pass or attack the locked chain
+        let locked = "4k3/8/8/1p1p1p1p/pPpPpPpP/P1P1P1P1/8/4K3 w - - 0 1";
+        let b = from_fen(locked).unwrap();
+        assert!(!b.is_insufficient_material());
+        assert!(b.is_dead_position(100_000));
+        assert!(!b.is_dead_position(100));
+        let b = from_fen("4k3/8/8/1p1p1p2/pPpPpPpP/P1P1P1P1/8/4K3 w - - 0 1").unwrap();
+        assert!(!b.is_dead_position(100_000));
+        let b = from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert!(b.is_dead_position(0));
+        let b = from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        assert!(!b.is_dead_position(100_000));
+    }
+
+    #[test]
+    fn checkmate_first() {
+        let b = from_fen("k5Q1/8/1K6/8/8/8/8/8 b - - 100 80").unwrap();
+        assert!(!b.is_fifty_move_claimable());
+        let b = from_fen("k5Q1/8/1K6/8/8/8/8/8 b - - 150 100").unwrap();
+        assert!(!b.is_seventy_five_move_draw());
+    }
+}
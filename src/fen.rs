@@ -1,10 +1,189 @@
 use crate::board::Board;
 use crate::field::{row, Field};
-use crate::piece::ColoredPiece;
+use crate::piece::{ColoredPiece, Side};
 use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
 use std::fmt::Write;
+use std::str::FromStr;
 
-fn to_fen(b: &Board) -> String {
+/// The six space separated fields of a FEN record
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FenField {
+    Placement,
+    Side,
+    Castling,
+    EnPassant,
+    HalfmoveClock,
+    FullMoves,
+}
+
+impl fmt::Display for FenField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenField::Placement => write!(f, "piece placement"),
+            FenField::Side => write!(f, "active color"),
+            FenField::Castling => write!(f, "castling availability"),
+            FenField::EnPassant => write!(f, "en passant square"),
+            FenField::HalfmoveClock => write!(f, "halfmove clock"),
+            FenField::FullMoves => write!(f, "fullmove number"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FenError {
+    /// Number of space separated fields found instead of six
+    FieldCount(usize),
+    /// Fields separated by anything but a single space
+    Separator,
+    /// Character which is neither a piece, a digit nor a rank separator
+    InvalidPiece {
+        rank: usize,
+        ch: char,
+    },
+    /// Rank which does not describe exactly eight files
+    RankLength {
+        rank: usize,
+    },
+    /// Rank with a zero or two consecutive empty square counts
+    EmptyCount {
+        rank: usize,
+    },
+    /// Number of ranks found instead of eight
+    RankCount(usize),
+    InvalidSide(String),
+    InvalidCastling(String),
+    InvalidEnPassant(String),
+    InvalidHalfmoveClock(String),
+    InvalidFullMoves(String),
+}
+
+impl FenError {
+    /// Returns the FEN field which failed to parse
+    pub fn field(&self) -> Option<FenField> {
+        match self {
+            FenError::FieldCount(_) | FenError::Separator => None,
+            FenError::InvalidPiece { .. }
+            | FenError::RankLength { .. }
+            | FenError::EmptyCount { .. }
+            | FenError::RankCount(_) => Some(FenField::Placement),
+            FenError::InvalidSide(_) => Some(FenField::Side),
+            FenError::InvalidCastling(_) => Some(FenField::Castling),
+            FenError::InvalidEnPassant(_) => Some(FenField::EnPassant),
+            FenError::InvalidHalfmoveClock(_) => Some(FenField::HalfmoveClock),
+            FenError::InvalidFullMoves(_) => Some(FenField::FullMoves),
+        }
+    }
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::FieldCount(n) => write!(f, "FEN should contain 6 fields, found {}", n),
+            FenError::Separator => write!(f, "FEN fields should be separated by single spaces"),
+            FenError::InvalidPiece { rank, ch } => write!(
+                f,
+                "Invalid piece '{}' in rank {} of {}",
+                ch,
+                rank,
+                FenField::Placement
+            ),
+            FenError::RankLength { rank } => write!(
+                f,
+                "Rank {} of {} does not have 8 files",
+                rank,
+                FenField::Placement
+            ),
+            FenError::EmptyCount { rank } => write!(
+                f,
+                "Rank {} of {} has a zero or split empty square count",
+                rank,
+                FenField::Placement
+            ),
+            FenError::RankCount(n) => {
+                write!(f, "Piece placement should contain 8 ranks, found {}", n)
+            }
+            FenError::InvalidSide(v) => write!(f, "Invalid {} '{}'", FenField::Side, v),
+            FenError::InvalidCastling(v) => write!(f, "Invalid {} '{}'", FenField::Castling, v),
+            FenError::InvalidEnPassant(v) => write!(f, "Invalid {} '{}'", FenField::EnPassant, v),
+            FenError::InvalidHalfmoveClock(v) => {
+                write!(f, "Invalid {} '{}'", FenField::HalfmoveClock, v)
+            }
+            FenError::InvalidFullMoves(v) => write!(f, "Invalid {} '{}'", FenField::FullMoves, v),
+        }
+    }
+}
+
+impl Error for FenError {}
+
+/// Deviation from the FEN grammar accepted by `from_fen_lenient`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FenCorrection {
+    /// Fields separated by whitespace other than single spaces
+    Whitespace,
+    /// Zero or split empty square counts summed up
+    EmptyCount { rank: usize },
+    /// Castling availability with unknown, repeated or unordered characters
+    /// reduced to the known ones
+    Castling(String),
+    /// En passant square which is not on the third or sixth rank dropped
+    EnPassant(String),
+    /// Missing halfmove clock and fullmove number set to 0 and 1
+    MissingClocks,
+    /// Fullmove number 0 set to 1
+    FullMoves,
+}
+
+impl fmt::Display for FenCorrection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenCorrection::Whitespace => write!(f, "Normalized whitespace between fields"),
+            FenCorrection::EmptyCount { rank } => {
+                write!(f, "Summed up empty square counts in rank {}", rank)
+            }
+            FenCorrection::Castling(v) => {
+                write!(
+                    f,
+                    "Ignored invalid characters of {} '{}'",
+                    FenField::Castling,
+                    v
+                )
+            }
+            FenCorrection::EnPassant(v) => {
+                write!(f, "Dropped invalid {} '{}'", FenField::EnPassant, v)
+            }
+            FenCorrection::MissingClocks => write!(f, "Added missing clocks '0 1'"),
+            FenCorrection::FullMoves => write!(f, "Set {} 0 to 1", FenField::FullMoves),
+        }
+    }
+}
+
+impl Board {
+    pub fn from_fen(s: &str) -> Result<Board, FenError> {
+        from_fen(s)
+    }
+
+    pub fn to_fen(&self) -> String {
+        to_fen(self)
+    }
+}
+
+impl FromStr for Board {
+    type Err = FenError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        from_fen(s)
+    }
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", to_fen(self))
+    }
+}
+
+pub fn to_fen(b: &Board) -> String {
     let mut rv = String::new();
     for r in (1..9).rev() {
         let mut empty_count: u8 = 0;
@@ -57,70 +236,181 @@ fn to_fen(b: &Board) -> String {
     rv
 }
 
-fn from_fen(s: &str) -> Result<Board, &'static str> {
-    let mut b = Board::empty();
-    let data: Vec<&str> = s.split(' ').collect();
-    if data.len() != 6 {
-        Err("Fen should contain 6 fields")?;
+/// Parses FEN following its grammar strictly
+pub fn from_fen(s: &str) -> Result<Board, FenError> {
+    let mut parser = Parser {
+        strict: true,
+        corrections: Vec::new(),
+    };
+    parser.parse(s)
+}
+
+/// Parses FEN accepting common deviations from its grammar, which are
+/// reported along with the board
+pub fn from_fen_lenient(s: &str) -> Result<(Board, Vec<FenCorrection>), FenError> {
+    let mut parser = Parser {
+        strict: false,
+        corrections: Vec::new(),
+    };
+    let b = parser.parse(s)?;
+    Ok((b, parser.corrections))
+}
+
+struct Parser {
+    strict: bool,
+    corrections: Vec<FenCorrection>,
+}
+
+impl Parser {
+    /// Fails with `e` in strict mode, otherwise records `c` and goes on
+    fn correct(&mut self, e: FenError, c: FenCorrection) -> Result<(), FenError> {
+        if self.strict {
+            return Err(e);
+        }
+        self.corrections.push(c);
+        Ok(())
     }
 
-    let field = data[0];
-    let color = data[1];
-    let castle = data[2];
-    let en_passant = data[3];
-    let halfmove_clock = data[4];
-    let full_moves = data[5];
-
-    let mut row = 8;
-    let mut column = 1;
-    for ch in field.chars() {
-        if ch == '/' {
+    fn parse(&mut self, s: &str) -> Result<Board, FenError> {
+        let mut b = Board::empty();
+        let mut data: Vec<&str> = s.split_whitespace().collect();
+        if data.join(" ") != s {
+            self.correct(FenError::Separator, FenCorrection::Whitespace)?;
+        }
+        if data.len() == 4 {
+            self.correct(FenError::FieldCount(4), FenCorrection::MissingClocks)?;
+            data.extend(&["0", "1"]);
+        }
+        if data.len() != 6 {
+            return Err(FenError::FieldCount(data.len()));
+        }
+
+        let placement = data[0];
+        let color = data[1];
+        let castle = data[2];
+        let en_passant = data[3];
+        let halfmove_clock = data[4];
+        let full_moves = data[5];
+
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::RankCount(ranks.len()));
+        }
+        for (idx, rank) in ranks.iter().enumerate() {
+            let row = 8 - idx;
+            let mut column = 1;
+            let mut after_count = false;
+            let mut corrected = false;
+            for ch in rank.chars() {
+                if let Some(count) = ch.to_digit(10) {
+                    if (count == 0 || after_count) && !corrected {
+                        corrected = true;
+                        self.correct(
+                            FenError::EmptyCount { rank: row },
+                            FenCorrection::EmptyCount { rank: row },
+                        )?;
+                    }
+                    column += count as usize;
+                    after_count = true;
+                    continue;
+                }
+                let piece = ColoredPiece::try_from(ch)
+                    .map_err(|_| FenError::InvalidPiece { rank: row, ch })?;
+                if column > 8 {
+                    return Err(FenError::RankLength { rank: row });
+                }
+                b.pieces[Field::new(row, column).0] = piece;
+                column += 1;
+                after_count = false;
+            }
             if column != 9 {
-                Err("Not all columns are present")?
+                return Err(FenError::RankLength { rank: row });
             }
-            row -= 1;
-            column = 1;
-            continue;
         }
-        if let Ok(count) = String::from(ch).parse::<usize>() {
-            column += count;
-            continue;
+
+        b.active = color
+            .parse()
+            .map_err(|_| FenError::InvalidSide(color.to_string()))?;
+
+        b.can_castle = match parse_castling(castle) {
+            Some(can_castle) => can_castle,
+            None => {
+                self.correct(
+                    FenError::InvalidCastling(castle.to_string()),
+                    FenCorrection::Castling(castle.to_string()),
+                )?;
+                let mut rv = [false; 4];
+                for (idx, ch) in "KQkq".chars().enumerate() {
+                    rv[idx] = castle.contains(ch);
+                }
+                rv
+            }
+        };
+
+        let en_passant_row = match b.active {
+            Side::White => 6,
+            Side::Black => 3,
+        };
+        b.en_passant = match en_passant.parse::<Field>() {
+            _ if en_passant == "-" => None,
+            Ok(f) if f.row() == en_passant_row => Some(f),
+            _ => {
+                self.correct(
+                    FenError::InvalidEnPassant(en_passant.to_string()),
+                    FenCorrection::EnPassant(en_passant.to_string()),
+                )?;
+                None
+            }
+        };
+
+        b.halfmove_clock = parse_number(halfmove_clock)
+            .ok_or_else(|| FenError::InvalidHalfmoveClock(halfmove_clock.to_string()))?;
+        b.full_moves = parse_number(full_moves)
+            .ok_or_else(|| FenError::InvalidFullMoves(full_moves.to_string()))?;
+        if b.full_moves == 0 {
+            self.correct(
+                FenError::InvalidFullMoves(full_moves.to_string()),
+                FenCorrection::FullMoves,
+            )?;
+            b.full_moves = 1;
         }
-        let piece = ColoredPiece::try_from(ch)?;
-        let f = Field::new(row, column);
-        b.pieces[f.0] = piece;
-        column += 1;
+
+        b.update_hash();
+        Ok(b)
     }
-    if row != 1 {
-        Err("Not all rows are present")?
+}
+
+/// Parses castling availability which is either '-' or a non-empty
+/// subsequence of "KQkq"
+fn parse_castling(s: &str) -> Option<[bool; 4]> {
+    let mut rv = [false; 4];
+    if s == "-" {
+        return Some(rv);
     }
-    if column != 9 {
-        Err("Not all columns are present")?
+    let mut next = 0;
+    for ch in s.chars() {
+        let idx = "KQkq".chars().skip(next).position(|x| x == ch)? + next;
+        rv[idx] = true;
+        next = idx + 1;
     }
+    if next == 0 {
+        None
+    } else {
+        Some(rv)
+    }
+}
 
-    b.active = color.parse()?;
-
-    b.can_castle[0] = castle.find('K').is_some();
-    b.can_castle[1] = castle.find('Q').is_some();
-    b.can_castle[2] = castle.find('k').is_some();
-    b.can_castle[3] = castle.find('q').is_some();
-
-    b.en_passant = en_passant.parse::<Field>().ok();
-    b.halfmove_clock = halfmove_clock
-        .parse::<u32>()
-        .map_err(|_x| "Invalid halfmove")?;
-    b.full_moves = full_moves
-        .parse::<u32>()
-        .map_err(|_x| "Invalid fullmoves")?;
-
-    Ok(b)
+fn parse_number(s: &str) -> Option<u32> {
+    if s.is_empty() || !s.chars().all(|ch| ch.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::field::named::*;
-    use crate::piece::Side;
 
     #[test]
     fn from_string1() {
@@ -131,14 +421,22 @@ mod tests {
 
     #[test]
     fn from_string2() {
-        let parsed = from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b K-kq a3 11 13");
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b K-kq a3 11 13";
+        assert_eq!(
+            from_fen(fen),
+            Err(FenError::InvalidCastling("K-kq".to_string()))
+        );
+        let parsed = from_fen_lenient(fen);
         let mut b = Board::initial();
         b.active = Side::Black;
         b.can_castle[1] = false;
         b.en_passant = Some(A3);
         b.halfmove_clock = 11;
         b.full_moves = 13;
-        assert_eq!(Ok(b), parsed);
+        assert_eq!(
+            Ok((b, vec![FenCorrection::Castling("K-kq".to_string())])),
+            parsed
+        );
     }
 
     #[test]
@@ -255,4 +553,195 @@ mod tests {
             "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq a3 12 11"
         );
     }
+
+    #[test]
+    fn parse_display() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+            "rnbq1bnr/pppkpppp/8/3p4/4P3/5N2/PPPP1PPP/RNBQKB1R w KQ - 2 3",
+            "8/8/8/8/8/8/8/8 b - - 99 120",
+        ];
+        for fen in &fens {
+            let b = fen.parse::<Board>().unwrap();
+            assert_eq!(b, Board::from_fen(fen).unwrap());
+            assert_eq!(b.to_string(), *fen);
+            assert_eq!(b.to_fen(), *fen);
+        }
+        assert_eq!(Board::initial().to_string(), fens[0]);
+        assert!("8/8/8 w - - 0 1".parse::<Board>().is_err());
+    }
+
+    #[test]
+    fn errors() {
+        let check = |fen: &str, expected: FenError| {
+            assert_eq!(from_fen(fen), Err(expected));
+        };
+        check(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0",
+            FenError::FieldCount(5),
+        );
+        check(
+            "rnbqkbnr/pppppppp/8/8/4X3/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            FenError::InvalidPiece { rank: 4, ch: 'X' },
+        );
+        check(
+            "rnbqkbnr/pppppppp/8/8/7/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            FenError::RankLength { rank: 4 },
+        );
+        check(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN w KQkq - 0 1",
+            FenError::RankLength { rank: 1 },
+        );
+        check(
+            "rnbqkbnr/pppppppp/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            FenError::RankCount(7),
+        );
+        check(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+            FenError::InvalidSide("x".to_string()),
+        );
+        check(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - -1 1",
+            FenError::InvalidHalfmoveClock("-1".to_string()),
+        );
+        check(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 one",
+            FenError::InvalidFullMoves("one".to_string()),
+        );
+    }
+
+    #[test]
+    fn error_display() {
+        let e = FenError::InvalidPiece { rank: 4, ch: 'X' };
+        assert_eq!(e.field(), Some(FenField::Placement));
+        assert_eq!(
+            e.to_string(),
+            "Invalid piece 'X' in rank 4 of piece placement"
+        );
+        let e = FenError::InvalidSide("x".to_string());
+        assert_eq!(e.field(), Some(FenField::Side));
+        assert_eq!(e.to_string(), "Invalid active color 'x'");
+        assert_eq!(FenError::FieldCount(5).field(), None);
+        let e: Box<dyn Error> = Box::new(FenError::RankCount(7));
+        assert_eq!(
+            e.to_string(),
+            "Piece placement should contain 8 ranks, found 7"
+        );
+    }
+
+    #[test]
+    fn strict() {
+        let check = |fen: &str, expected: FenError| {
+            assert_eq!(from_fen(fen), Err(expected.clone()), "{}", fen);
+        };
+        check(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR  w KQkq - 0 1",
+            FenError::Separator,
+        );
+        check(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\n",
+            FenError::Separator,
+        );
+        check(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -",
+            FenError::FieldCount(4),
+        );
+        check(
+            "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            FenError::RankLength { rank: 6 },
+        );
+        check(
+            "rnbqkbnr/ppppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            FenError::RankLength { rank: 7 },
+        );
+        check(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR/8 w KQkq - 0 1",
+            FenError::RankCount(9),
+        );
+        check(
+            "rnbqkbnr/pppppppp/8/08/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            FenError::EmptyCount { rank: 5 },
+        );
+        check(
+            "rnbqkbnr/pppppppp/8/44/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            FenError::EmptyCount { rank: 5 },
+        );
+        for castling in &["K-kq", "kK", "KK", "KQkqx", "--"] {
+            check(
+                &format!(
+                    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w {} - 0 1",
+                    castling
+                ),
+                FenError::InvalidCastling(castling.to_string()),
+            );
+        }
+        for en_passant in &["x9", "e3", "e4", "E6"] {
+            check(
+                &format!(
+                    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq {} 0 1",
+                    en_passant
+                ),
+                FenError::InvalidEnPassant(en_passant.to_string()),
+            );
+        }
+        check(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - +1 1",
+            FenError::InvalidHalfmoveClock("+1".to_string()),
+        );
+        check(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 0",
+            FenError::InvalidFullMoves("0".to_string()),
+        );
+        for castling in &["-", "K", "Qk", "KQkq", "Kq"] {
+            let fen = format!("8/8/8/8/8/8/8/8 w {} e6 0 1", castling);
+            assert_eq!(from_fen(&fen).map(|b| b.to_string()), Ok(fen));
+        }
+    }
+
+    #[test]
+    fn lenient() {
+        let check = |fen: &str, expected: &str, corrections: &[FenCorrection]| {
+            let (b, c) = from_fen_lenient(fen).unwrap();
+            assert_eq!(to_fen(&b), expected);
+            assert_eq!(c, corrections);
+        };
+        let initial = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        check(initial, initial, &[]);
+        check(
+            " rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR  w\tKQkq - 0 1\n",
+            initial,
+            &[FenCorrection::Whitespace],
+        );
+        check(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -",
+            initial,
+            &[FenCorrection::MissingClocks],
+        );
+        check(
+            "rnbqkbnr/pppppppp/8/08/44/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            initial,
+            &[
+                FenCorrection::EmptyCount { rank: 5 },
+                FenCorrection::EmptyCount { rank: 4 },
+            ],
+        );
+        check(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w qkQK x9 0 0",
+            initial,
+            &[
+                FenCorrection::Castling("qkQK".to_string()),
+                FenCorrection::EnPassant("x9".to_string()),
+                FenCorrection::FullMoves,
+            ],
+        );
+        assert_eq!(
+            from_fen_lenient("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            Err(FenError::RankLength { rank: 6 })
+        );
+        assert_eq!(
+            FenCorrection::Castling("K-kq".to_string()).to_string(),
+            "Ignored invalid characters of castling availability 'K-kq'"
+        );
+    }
 }
@@ -37,6 +37,10 @@ impl Field {
     pub fn row(&self) -> usize {
         1 + (self.0 / 8)
     }
+
+    pub fn column(&self) -> usize {
+        1 + (self.0 % 8)
+    }
 }
 
 pub mod named {
@@ -157,7 +161,7 @@ pub fn row(r: usize) -> RowIter {
 }
 
 #[rustfmt::skip]
-const NAMES: [&'static str; 64] = [
+const NAMES: [&str; 64] = [
     "a1", "b1", "c1", "d1", "e1", "f1", "g1", "h1",
     "a2", "b2", "c2", "d2", "e2", "f2", "g2", "h2",
     "a3", "b3", "c3", "d3", "e3", "f3", "g3", "h3",
@@ -188,6 +192,13 @@ mod tests {
         assert_eq!(Field::new(8, 6), F8);
     }
 
+    #[test]
+    fn row_column() {
+        assert_eq!((A1.row(), A1.column()), (1, 1));
+        assert_eq!((D2.row(), D2.column()), (2, 4));
+        assert_eq!((H8.row(), H8.column()), (8, 8));
+    }
+
     #[test]
     fn parse() {
         assert_eq!(Field::from_str("a1"), Ok(A1));
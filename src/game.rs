@@ -0,0 +1,317 @@
+use crate::board::Board;
+use crate::move_generator::generate_legal;
+use crate::mv::Move;
+use crate::outcome::{Outcome, Termination};
+use crate::piece::Side;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum GameError {
+    /// Move which is not legal in the current position
+    Illegal(Move),
+    /// Move after the end of the game
+    Finished(Outcome),
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::Illegal(m) => write!(f, "Illegal move '{}'", m),
+            GameError::Finished(o) => write!(f, "Game is over: {}", o.result),
+        }
+    }
+}
+
+impl Error for GameError {}
+
+/// Moves played from a starting position with the positions they lead to.
+/// The current ply can be moved back and forth through the history; a move
+/// pushed before the end replaces the moves after it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Game {
+    moves: Vec<Move>,
+    /// Starting position followed by the position after each move
+    positions: Vec<Board>,
+    ply: usize,
+    /// Outcome set by the caller, which only holds for the whole history
+    declared: Option<Outcome>,
+}
+
+impl Game {
+    pub fn new(start: Board) -> Self {
+        Game {
+            moves: Vec::new(),
+            positions: vec![start],
+            ply: 0,
+            declared: None,
+        }
+    }
+
+    pub fn start(&self) -> &Board {
+        &self.positions[0]
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    /// Number of moves played
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    /// Current ply, 0 for the starting position
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    /// Position at the current ply
+    pub fn board(&self) -> &Board {
+        &self.positions[self.ply]
+    }
+
+    /// Position after `ply` moves
+    pub fn position(&self, ply: usize) -> Option<&Board> {
+        self.positions.get(ply)
+    }
+
+    /// Makes `ply` the current ply, returns false if no such ply was played
+    pub fn go_to(&mut self, ply: usize) -> bool {
+        if ply > self.moves.len() {
+            return false;
+        }
+        self.ply = ply;
+        true
+    }
+
+    /// Plays a legal move at the current ply and makes the new position
+    /// current. Moves after the current ply are dropped.
+    pub fn push(&mut self, m: Move) -> Result<(), GameError> {
+        if self.ply == self.moves.len() {
+            if let Some(outcome) = self.outcome() {
+                return Err(GameError::Finished(outcome));
+            }
+        }
+        if !generate_legal(self.board()).contains(&m) {
+            return Err(GameError::Illegal(m));
+        }
+        let mut b = self.board().clone();
+        b.make_move(m);
+        self.truncate(self.ply);
+        self.moves.push(m);
+        self.positions.push(b);
+        self.ply += 1;
+        Ok(())
+    }
+
+    /// Takes back the last move of the game
+    pub fn pop(&mut self) -> Option<Move> {
+        let m = self.moves.pop()?;
+        self.positions.pop();
+        self.ply = self.ply.min(self.moves.len());
+        self.declared = None;
+        Some(m)
+    }
+
+    fn truncate(&mut self, ply: usize) {
+        if ply < self.moves.len() {
+            self.moves.truncate(ply);
+            self.positions.truncate(ply + 1);
+            self.declared = None;
+        }
+    }
+
+    /// Ends the game by resignation of `side`
+    pub fn resign(&mut self, side: Side) {
+        self.declared = Some(Outcome::win(side.opposite(), Termination::Resignation));
+    }
+
+    /// Ends the game because `side` ran out of time. It is a draw when the
+    /// opponent has no mating material.
+    pub fn timeout(&mut self, side: Side) {
+        let winner = side.opposite();
+        self.declared = Some(
+            if self.positions[self.moves.len()].has_mating_material(winner) {
+                Outcome::win(winner, Termination::Timeout)
+            } else {
+                Outcome::draw(Termination::Timeout)
+            },
+        );
+    }
+
+    /// Ends the game in a draw claimed or agreed by the players
+    pub fn claim_draw(&mut self) {
+        self.declared = Some(Outcome::draw(Termination::DrawClaimed));
+    }
+
+    /// Claims a draw by threefold repetition, returns false if the position
+    /// did not occur three times
+    pub fn claim_repetition(&mut self) -> bool {
+        if !self.is_threefold_repetition() {
+            return false;
+        }
+        self.declared = Some(Outcome::draw(Termination::ThreefoldRepetition));
+        true
+    }
+
+    /// Claims a draw by the fifty-move rule, returns false if it does not
+    /// apply after the last move
+    pub fn claim_fifty_moves(&mut self) -> bool {
+        if !self.positions[self.moves.len()].is_fifty_move_claimable() {
+            return false;
+        }
+        self.declared = Some(Outcome::draw(Termination::FiftyMoves));
+        true
+    }
+
+    /// Outcome after the last move: checkmate, stalemate, insufficient
+    /// material, fivefold repetition or the seventy-five-move rule, otherwise
+    /// a resignation, timeout or draw set by the caller
+    pub fn outcome(&self) -> Option<Outcome> {
+        let b = &self.positions[self.moves.len()];
+        if b.is_checkmate() {
+            return Some(Outcome::win(b.active.opposite(), Termination::Checkmate));
+        }
+        if b.is_stalemate() {
+            return Some(Outcome::draw(Termination::Stalemate));
+        }
+        if b.is_insufficient_material() {
+            return Some(Outcome::draw(Termination::InsufficientMaterial));
+        }
+        if self.is_fivefold_repetition() {
+            return Some(Outcome::draw(Termination::FivefoldRepetition));
+        }
+        if b.is_seventy_five_move_draw() {
+            return Some(Outcome::draw(Termination::SeventyFiveMoves));
+        }
+        self.declared
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::{from_fen, to_fen};
+    use crate::outcome::GameResult;
+
+    fn play(game: &mut Game, moves: &[&str]) {
+        for m in moves {
+            let m = Move::from_san(m, game.board()).unwrap();
+            game.push(m).unwrap();
+        }
+    }
+
+    #[test]
+    fn history() {
+        let mut game = Game::new(Board::initial());
+        assert!(game.is_empty());
+        play(&mut game, &["e4", "e5", "Nf3"]);
+        assert_eq!((game.len(), game.ply()), (3, 3));
+        assert_eq!(
+            to_fen(game.board()),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+        );
+        let e4 = game.moves()[0];
+        assert_eq!(game.push(e4), Err(GameError::Illegal(e4)));
+
+        assert!(game.go_to(1));
+        assert_eq!(game.board(), game.position(1).unwrap());
+        assert_eq!(game.len(), 3);
+        assert!(!game.go_to(4));
+        assert_eq!(game.position(4), None);
+
+        // Playing from an earlier ply replaces the rest of the game
+        play(&mut game, &["c5"]);
+        assert_eq!(game.len(), 2);
+        let c5 = game.moves()[1];
+        assert_eq!(game.pop(), Some(c5));
+        assert_eq!(game.pop(), Some(e4));
+        assert_eq!(game.pop(), None);
+        assert_eq!(game.board(), &Board::initial());
+        assert_eq!(game.start(), &Board::initial());
+    }
+
+    #[test]
+    fn outcome() {
+        let mut game = Game::new(Board::initial());
+        play(&mut game, &["f3", "e5", "g4"]);
+        assert_eq!(game.outcome(), None);
+        play(&mut game, &["Qh4#"]);
+        let mate = Outcome::win(Side::Black, Termination::Checkmate);
+        assert_eq!(game.outcome(), Some(mate));
+        // The outcome is the one of the whole game, not the current ply
+        game.go_to(2);
+        assert_eq!(game.outcome(), Some(mate));
+
+        let b = from_fen("k7/8/1Q6/8/8/8/8/7K w - - 0 1").unwrap();
+        let mut game = Game::new(b);
+        play(&mut game, &["Qb5"]);
+        assert_eq!(game.outcome(), None);
+        game.pop();
+        play(&mut game, &["Qc7"]);
+        assert_eq!(game.outcome(), Some(Outcome::draw(Termination::Stalemate)));
+
+        let b = from_fen("4k3/8/8/8/8/8/8/4K2R w - - 99 100").unwrap();
+        let mut game = Game::new(b);
+        assert!(!game.claim_fifty_moves());
+        play(&mut game, &["Rh2"]);
+        assert!(game.claim_fifty_moves());
+        assert_eq!(game.outcome(), Some(Outcome::draw(Termination::FiftyMoves)));
+
+        let b = from_fen("4k3/8/8/8/8/8/8/4K2R w - - 149 100").unwrap();
+        let mut game = Game::new(b);
+        play(&mut game, &["Rh2"]);
+        let draw = Outcome::draw(Termination::SeventyFiveMoves);
+        assert_eq!(game.outcome(), Some(draw));
+        let e7 = Move::from_san("Ke7", game.board()).unwrap();
+        assert_eq!(game.push(e7), Err(GameError::Finished(draw)));
+    }
+
+    #[test]
+    fn declared() {
+        let mut game = Game::new(Board::initial());
+        play(&mut game, &["e4"]);
+        game.resign(Side::Black);
+        let outcome = game.outcome().unwrap();
+        assert_eq!(outcome.result, GameResult::WhiteWins);
+        assert_eq!(outcome.termination, Termination::Resignation);
+        let e5 = Move::from_san("e5", game.board()).unwrap();
+        assert_eq!(game.push(e5), Err(GameError::Finished(outcome)));
+
+        // Taking back a move also takes back the resignation
+        game.pop();
+        assert_eq!(game.outcome(), None);
+        game.timeout(Side::White);
+        assert_eq!(game.outcome().unwrap().result, GameResult::BlackWins);
+        game.claim_draw();
+        assert_eq!(
+            game.outcome(),
+            Some(Outcome::draw(Termination::DrawClaimed))
+        );
+
+        // A lone knight cannot win on time
+        let b = from_fen("4k3/8/8/8/8/8/8/3qKN2 w - - 0 1").unwrap();
+        let mut game = Game::new(b.clone());
+        game.timeout(Side::Black);
+        assert_eq!(game.outcome(), Some(Outcome::draw(Termination::Timeout)));
+        let mut game = Game::new(b);
+        game.timeout(Side::White);
+        assert_eq!(game.outcome().unwrap().result, GameResult::BlackWins);
+    }
+
+    #[test]
+    fn insufficient_material() {
+        let b = from_fen("4k3/8/8/8/8/8/3q4/4KN2 w - - 0 1").unwrap();
+        let mut game = Game::new(b);
+        play(&mut game, &["Kxd2"]);
+        assert_eq!(
+            game.outcome(),
+            Some(Outcome::draw(Termination::InsufficientMaterial))
+        );
+    }
+}
@@ -1,8 +1,41 @@
-#![allow(dead_code)]
-
 mod board;
+mod draw;
 mod fen;
 mod field;
+mod game;
 mod move_generator;
 mod mv;
+mod outcome;
+mod perft;
+mod pgn;
 mod piece;
+mod polyglot;
+mod repetition;
+mod san;
+mod uci;
+mod validate;
+mod zobrist;
+
+pub use crate::board::{Board, Undo};
+pub use crate::fen::{from_fen, from_fen_lenient, to_fen, FenCorrection, FenError, FenField};
+pub use crate::field::named as fields;
+pub use crate::field::Field;
+pub use crate::game::{Game, GameError};
+pub use crate::move_generator::{generate, generate_legal};
+pub use crate::mv::{
+    capture, castle, double_push, en_passant, king_castle, mv, queen_castle, CastleData, Move,
+    MoveData, PackedMove,
+};
+pub use crate::outcome::{GameResult, Outcome, Termination};
+pub use crate::perft::{divide, perft};
+pub use crate::pgn::{
+    from_pgn, to_pgn, PgnError, PgnGame, PgnMove, PgnOptions, PgnReader, PgnVisitor,
+};
+pub use crate::piece::named as pieces;
+pub use crate::piece::{ColoredPiece, Piece, Side};
+pub use crate::polyglot::{
+    polyglot_key, Book, BookBuilder, BookEntry, PolyglotError, PolyglotKeys, RANDOM_COUNT,
+};
+pub use crate::san::SanError;
+pub use crate::uci::UciError;
+pub use crate::validate::PositionError;
@@ -1,8 +1,14 @@
 use crate::board::Board;
 use crate::field::{fields, Field};
-use crate::mv::{capture, mv, Move};
+use crate::mv::{capture, double_push, en_passant, king_castle, mv, queen_castle, Move};
 use crate::piece::{ColoredPiece, Piece, Side};
 
+const KING_OFFSETS: [isize; 8] = [-11, -10, -9, -1, 1, 9, 10, 11];
+const ROOK_OFFSETS: [isize; 4] = [-10, -1, 1, 10];
+const BISHOP_OFFSETS: [isize; 4] = [-11, -9, 9, 11];
+const KNIGHT_OFFSETS: [isize; 8] = [-21, -19, -12, -8, 8, 12, 19, 21];
+
+/// Generates pseudo-legal moves: the king of the side to move may be left in check
 pub fn generate(b: &Board) -> Vec<Move> {
     let mut rv: Vec<Move> = Vec::new();
     for idx in fields() {
@@ -12,11 +18,11 @@ pub fn generate(b: &Board) -> Vec<Move> {
             }
             let mut piece_moves = |moves, slide| gen_piece_moves(idx, b, moves, slide, &mut rv);
             match piece {
-                Piece::King => piece_moves(&[-11, -10, -9, -1, 1, 9, 10, 11], false),
-                Piece::Queen => piece_moves(&[-11, -10, -9, -1, 1, 9, 10, 11], true),
-                Piece::Rook => piece_moves(&[-10, -1, 1, 10], true),
-                Piece::Bishop => piece_moves(&[-11, -9, 9, 11], true),
-                Piece::Knight => piece_moves(&[-21, -19, -12, -8, 8, 12, 19, 21], false),
+                Piece::King => piece_moves(&KING_OFFSETS, false),
+                Piece::Queen => piece_moves(&KING_OFFSETS, true),
+                Piece::Rook => piece_moves(&ROOK_OFFSETS, true),
+                Piece::Bishop => piece_moves(&BISHOP_OFFSETS, true),
+                Piece::Knight => piece_moves(&KNIGHT_OFFSETS, false),
                 Piece::Pawn => {
                     let mult = match b.active {
                         Side::White => 1,
@@ -48,43 +54,125 @@ pub fn generate(b: &Board) -> Vec<Move> {
             }
         }
     }
-    let can_castle = |king_from: Field,
-                      king_to: Field,
-                      rook_from: Field,
-                      rook_to: Field,
-                      other: Option<Field>|
-     -> bool {
+    // The fields between the king and the rook and on their paths must be
+    // empty, and the king may not castle out of, through or into check
+    let can_castle = |m: Move| -> bool {
+        let (king_from, king_to, rook_from, rook_to) =
+            m.castle_fields().expect("Castling move has fields");
+        let row = king_from.row();
+        let span = |a: Field, b: Field| {
+            (a.column().min(b.column())..=a.column().max(b.column()))
+                .map(move |c| Field::new(row, c))
+        };
+        let opponent = b.active.opposite();
         b.pieces[king_from.0] == ColoredPiece::P(Piece::King, b.active)
-            && b.pieces[king_to.0] == ColoredPiece::Empty
             && b.pieces[rook_from.0] == ColoredPiece::P(Piece::Rook, b.active)
-            && b.pieces[rook_to.0] == ColoredPiece::Empty
-            && match other {
-                None => true,
-                Some(f) => b.pieces[f.0] == ColoredPiece::Empty,
-            }
+            && span(king_from, king_to)
+                .chain(span(rook_from, rook_to))
+                .all(|f| f == king_from || f == rook_from || b.pieces[f.0] == ColoredPiece::Empty)
+            && span(king_from, king_to).all(|f| !b.is_attacked(f, opponent))
     };
-    use crate::field::named::*;
-    match b.active {
-        Side::White => {
-            if b.can_castle[0] && can_castle(E1, G1, H1, F1, None) {
-                rv.push(Move::KingCastle);
-            }
-            if b.can_castle[1] && can_castle(E1, C1, A1, D1, Some(B1)) {
-                rv.push(Move::QueenCastle);
-            }
-        }
-        Side::Black => {
-            if b.can_castle[2] && can_castle(E8, G8, H8, F8, None) {
-                rv.push(Move::KingCastle);
-            }
-            if b.can_castle[3] && can_castle(E8, C8, A8, D8, Some(B8)) {
-                rv.push(Move::QueenCastle);
-            }
+    let rights = match b.active {
+        Side::White => [b.can_castle[0], b.can_castle[1]],
+        Side::Black => [b.can_castle[2], b.can_castle[3]],
+    };
+    let castles = [king_castle(b.active), queen_castle(b.active)];
+    for (allowed, m) in rights.iter().zip(castles.iter()) {
+        if *allowed && can_castle(*m) {
+            rv.push(*m);
         }
     }
     rv
 }
 
+/// Generates legal moves: moves leaving the king of the side to move in check are dropped
+pub fn generate_legal(b: &Board) -> Vec<Move> {
+    let mut after = b.clone();
+    generate(b)
+        .into_iter()
+        .filter(|m| {
+            let undo = after.make_move(*m);
+            let legal = !in_check(&after, b.active);
+            after.unmake_move(*m, undo);
+            legal
+        })
+        .collect()
+}
+
+/// Checks whether the king of `side` is attacked. Positions without a king
+/// are never in check.
+pub(crate) fn in_check(b: &Board, side: Side) -> bool {
+    match find_king(b, side) {
+        Some(king) => b.is_attacked(king, side.opposite()),
+        None => false,
+    }
+}
+
+/// Checks whether field `f` is attacked by any piece of side `by`
+pub(crate) fn is_attacked(b: &Board, f: Field, by: Side) -> bool {
+    attack_rays(by).iter().any(|(offsets, is_sliding, pieces)| {
+        offsets
+            .iter()
+            .any(|offset| ray_attacker(b, f, *offset, *is_sliding, by, pieces).is_some())
+    })
+}
+
+/// Fields of the pieces of side `by` which attack field `f`
+pub(crate) fn attackers(b: &Board, f: Field, by: Side) -> Vec<Field> {
+    attack_rays(by)
+        .iter()
+        .flat_map(|(offsets, is_sliding, pieces)| {
+            offsets
+                .iter()
+                .filter_map(move |offset| ray_attacker(b, f, *offset, *is_sliding, by, pieces))
+        })
+        .collect()
+}
+
+type AttackRay = (&'static [isize], bool, &'static [Piece]);
+
+/// Directions to look for attackers of side `by` with the pieces attacking
+/// along them. Pawns attack forward, so they are looked for behind the field.
+fn attack_rays(by: Side) -> [AttackRay; 5] {
+    let pawn_offsets: &[isize] = match by {
+        Side::White => &[-9, -11],
+        Side::Black => &[9, 11],
+    };
+    [
+        (pawn_offsets, false, &[Piece::Pawn]),
+        (&KNIGHT_OFFSETS, false, &[Piece::Knight]),
+        (&KING_OFFSETS, false, &[Piece::King]),
+        (&ROOK_OFFSETS, true, &[Piece::Rook, Piece::Queen]),
+        (&BISHOP_OFFSETS, true, &[Piece::Bishop, Piece::Queen]),
+    ]
+}
+
+/// Returns the first piece from `f` in direction `offset` if it is one of
+/// `pieces` of side `by`
+fn ray_attacker(
+    b: &Board,
+    f: Field,
+    offset: isize,
+    is_sliding: bool,
+    by: Side,
+    pieces: &[Piece],
+) -> Option<Field> {
+    let mut n = f.0;
+    loop {
+        n = move64(n, offset)?;
+        match b.pieces[n] {
+            ColoredPiece::P(p, s) if s == by && pieces.contains(&p) => return Some(Field(n)),
+            ColoredPiece::P(_, _) => return None,
+            ColoredPiece::Empty if !is_sliding => return None,
+            ColoredPiece::Empty => continue,
+        }
+    }
+}
+
+pub(crate) fn find_king(b: &Board, side: Side) -> Option<Field> {
+    fields().find(|f| b.pieces[f.0] == ColoredPiece::P(Piece::King, side))
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 
 const MX: usize = usize::MAX;
@@ -133,7 +221,7 @@ fn gen_pawn_moves(
     rv: &mut Vec<Move>,
 ) {
     let mut n = idx.0;
-    for offset in move_offsets {
+    for (i, offset) in move_offsets.iter().enumerate() {
         n = match move64(n, *offset) {
             Some(rv) => rv,
             None => continue,
@@ -142,6 +230,10 @@ fn gen_pawn_moves(
         if ColoredPiece::Empty != b.pieces[f.0] {
             break;
         }
+        if i == 1 {
+            rv.push(double_push(idx, f));
+            continue;
+        }
         for p in promotes {
             rv.push(mv(idx, f, *p));
         }
@@ -153,9 +245,7 @@ fn gen_pawn_moves(
             None => continue,
         };
         if b.en_passant == Some(f) {
-            for p in promotes {
-                rv.push(capture(idx, f, *p));
-            }
+            rv.push(en_passant(idx, f));
         } else if let ColoredPiece::P(_, c) = b.pieces[f.0] {
             if c != b.active {
                 for p in promotes {
@@ -194,6 +284,7 @@ mod tests {
     use super::*;
     use crate::board::Board;
     use crate::field::named;
+    use crate::mv::MoveData;
     use crate::piece::ColoredPiece;
     use crate::piece::Side;
     use crate::piece::Side::*;
@@ -231,17 +322,60 @@ mod tests {
         can_castle: [bool; 4],
         en_passant: Option<Field>,
     ) {
-        let mut expected_moves = HashSet::<Move>::new();
-        for mv in expected {
-            let mv: Move = mv.parse::<Move>().unwrap();
-            expected_moves.insert(mv);
-        }
+        test_generator(generate, side, pieces_str, expected, can_castle, en_passant);
+    }
+
+    fn test_legal_moves(
+        side: Side,
+        pieces_str: &[&str],
+        expected: &[&str],
+        can_castle: [bool; 4],
+        en_passant: Option<Field>,
+    ) {
+        test_generator(
+            generate_legal,
+            side,
+            pieces_str,
+            expected,
+            can_castle,
+            en_passant,
+        );
+    }
+
+    fn test_generator(
+        generator: fn(&Board) -> Vec<Move>,
+        side: Side,
+        pieces_str: &[&str],
+        expected: &[&str],
+        can_castle: [bool; 4],
+        en_passant: Option<Field>,
+    ) {
         let mut pieces = Vec::new();
         for pieces_str in pieces_str {
             pieces.push(piece(pieces_str));
         }
         let b = Board::new(&pieces, side, can_castle, en_passant, 0, 1);
-        let moves = generate(&b);
+        let mut expected_moves = HashSet::<Move>::new();
+        for mv in expected {
+            let mv: Move = match *mv {
+                "O-O" => king_castle(side),
+                "O-O-O" => queen_castle(side),
+                _ => mv.parse::<Move>().unwrap(),
+            };
+            // Pawn move kinds follow from the board
+            let is_pawn = |d: MoveData| b.pieces[d.from().0] == ColoredPiece::P(Piece::Pawn, side);
+            let mv = match mv {
+                Move::Move(d) if is_pawn(d) && d.from().row().abs_diff(d.to().row()) == 2 => {
+                    double_push(d.from(), d.to())
+                }
+                Move::Capture(d) if is_pawn(d) && en_passant == Some(d.to()) => {
+                    super::en_passant(d.from(), d.to())
+                }
+                _ => mv,
+            };
+            expected_moves.insert(mv);
+        }
+        let moves = generator(&b);
         let moves: HashSet<_> = moves.iter().cloned().collect();
         let expected = expected_moves;
         let redundant: Vec<_> = moves.difference(&expected).collect();
@@ -443,4 +577,189 @@ mod tests {
             None,
         );
     }
+
+    #[test]
+    fn castle_attacked() {
+        let king_moves = ["e1d1", "e1d2", "e1e2", "e1f1", "e1f2"];
+        let rook_moves = [
+            "h1f1", "h1g1", "h1h2", "h1h3", "h1h4", "h1h5", "h1h6", "h1h7", "h1h8",
+        ];
+        let expected = |castle: bool| -> Vec<&str> {
+            let mut rv: Vec<&str> = king_moves.iter().chain(&rook_moves).cloned().collect();
+            if castle {
+                rv.push("O-O");
+            }
+            rv
+        };
+        // Out of check
+        test_moves(
+            White,
+            &["Ke1", "Rh1", "re8"],
+            &expected(false),
+            [true, true, true, true],
+            None,
+        );
+        // Through check
+        test_moves(
+            White,
+            &["Ke1", "Rh1", "rf8"],
+            &expected(false),
+            [true, true, true, true],
+            None,
+        );
+        // Into check
+        test_moves(
+            White,
+            &["Ke1", "Rh1", "rg8"],
+            &expected(false),
+            [true, true, true, true],
+            None,
+        );
+        test_moves(
+            White,
+            &["Ke1", "Rh1", "ph2"],
+            &[
+                "e1d1", "e1d2", "e1e2", "e1f1", "e1f2", "h1f1", "h1g1", "h1xh2",
+            ],
+            [true, true, true, true],
+            None,
+        );
+        // Attacked squares beside the king's path don't matter
+        test_moves(
+            White,
+            &["Ke1", "Rh1", "rh8"],
+            &[
+                "O-O", "e1d1", "e1d2", "e1e2", "e1f1", "e1f2", "h1f1", "h1g1", "h1h2", "h1h3",
+                "h1h4", "h1h5", "h1h6", "h1h7", "h1xh8",
+            ],
+            [true, true, true, true],
+            None,
+        );
+        test_moves(
+            White,
+            &["Ke1", "Ra1", "rb8"],
+            &[
+                "O-O-O", "e1d1", "e1d2", "e1e2", "e1f1", "e1f2", "a1b1", "a1c1", "a1d1", "a1a2",
+                "a1a3", "a1a4", "a1a5", "a1a6", "a1a7", "a1a8",
+            ],
+            [true, true, true, true],
+            None,
+        );
+        test_moves(
+            Black,
+            &["ke8", "ra8", "Bg5"],
+            &[
+                "e8d8", "e8d7", "e8e7", "e8f7", "e8f8", "a8b8", "a8c8", "a8d8", "a8a7", "a8a6",
+                "a8a5", "a8a4", "a8a3", "a8a2", "a8a1",
+            ],
+            [true, true, true, true],
+            None,
+        );
+    }
+
+    #[test]
+    fn attacks() {
+        let b = Board::new(
+            &[
+                piece("Pe4"),
+                piece("nc6"),
+                piece("Rb1"),
+                piece("bh2"),
+                piece("ke8"),
+            ],
+            White,
+            [false, false, false, false],
+            None,
+            0,
+            1,
+        );
+        assert!(is_attacked(&b, named::D5, White));
+        assert!(is_attacked(&b, named::F5, White));
+        assert!(!is_attacked(&b, named::E5, White));
+        assert!(is_attacked(&b, named::B8, White));
+        assert!(is_attacked(&b, named::E5, Black));
+        assert!(is_attacked(&b, named::B8, Black));
+        assert!(is_attacked(&b, named::D7, Black));
+        assert!(is_attacked(&b, named::G1, Black));
+        assert!(!is_attacked(&b, named::F3, Black));
+        assert!(!is_attacked(&b, named::A1, Black));
+    }
+
+    #[test]
+    fn legal_pin() {
+        test_legal_moves(
+            White,
+            &["Ke1", "Re2", "re8", "ka8"],
+            &[
+                "e1d1", "e1d2", "e1f1", "e1f2", "e2e3", "e2e4", "e2e5", "e2e6", "e2e7", "e2xe8",
+            ],
+            [false, false, false, false],
+            None,
+        );
+        test_legal_moves(
+            White,
+            &["Ka1", "Bb2", "qh8", "kh1"],
+            &[
+                "a1a2", "a1b1", "b2c3", "b2d4", "b2e5", "b2f6", "b2g7", "b2xh8",
+            ],
+            [false, false, false, false],
+            None,
+        );
+    }
+
+    #[test]
+    fn legal_check() {
+        test_legal_moves(
+            White,
+            &["Ke1", "Nc3", "Pa2", "re8", "ka8"],
+            &["e1d1", "e1d2", "e1f1", "e1f2", "c3e2", "c3e4"],
+            [false, false, false, false],
+            None,
+        );
+        test_legal_moves(
+            Black,
+            &["ke8", "Re1", "Ra7", "Qh5"],
+            &["e8d8", "e8f8"],
+            [false, false, false, false],
+            None,
+        );
+    }
+
+    #[test]
+    fn legal_king() {
+        test_legal_moves(
+            White,
+            &["Ke1", "pe3", "ka8"],
+            &["e1d1", "e1e2", "e1f1"],
+            [false, false, false, false],
+            None,
+        );
+        test_legal_moves(
+            White,
+            &["Kd4", "kd6"],
+            &["d4c3", "d4d3", "d4e3", "d4c4", "d4e4"],
+            [false, false, false, false],
+            None,
+        );
+    }
+
+    #[test]
+    fn legal_en_passant() {
+        test_legal_moves(
+            White,
+            &["Ka5", "Pb5", "pc5", "rh5", "ka8"],
+            &["a5a4", "a5a6", "a5b6", "b5b6"],
+            [false, false, false, false],
+            Some(named::C6),
+        );
+        test_legal_moves(
+            White,
+            &["Ke4", "Pe5", "pd5", "ka8"],
+            &[
+                "e4d3", "e4e3", "e4f3", "e4d4", "e4f4", "e4f5", "e4xd5", "e5xd6",
+            ],
+            [false, false, false, false],
+            Some(named::D6),
+        );
+    }
 }
@@ -1,5 +1,6 @@
-use crate::field::Field;
-use crate::piece::Piece;
+use crate::field::{named::*, Field};
+use crate::piece::{Piece, Side};
+use std::convert::TryFrom;
 use std::fmt;
 use std::str::FromStr;
 
@@ -10,30 +11,210 @@ pub struct MoveData {
     promotion: Option<Piece>,
 }
 
+impl MoveData {
+    pub fn from(&self) -> Field {
+        self.from
+    }
+
+    pub fn to(&self) -> Field {
+        self.to
+    }
+
+    pub fn promotion(&self) -> Option<Piece> {
+        self.promotion
+    }
+}
+
+/// Fields of the king and the rook taking part in castling
+#[derive(Debug, Eq, PartialEq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub struct CastleData {
+    king_from: Field,
+    king_to: Field,
+    rook_from: Field,
+    rook_to: Field,
+}
+
+impl CastleData {
+    pub fn king_from(&self) -> Field {
+        self.king_from
+    }
+
+    pub fn king_to(&self) -> Field {
+        self.king_to
+    }
+
+    pub fn rook_from(&self) -> Field {
+        self.rook_from
+    }
+
+    pub fn rook_to(&self) -> Field {
+        self.rook_to
+    }
+}
+
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Eq, PartialEq, PartialOrd, Ord, Clone, Copy, Hash)]
 pub enum Move {
     Capture(MoveData),
     Move(MoveData),
-    KingCastle,
-    QueenCastle,
+    EnPassant(MoveData),
+    DoublePush(MoveData),
+    Promotion(MoveData),
+    PromotionCapture(MoveData),
+    KingCastle(CastleData),
+    QueenCastle(CastleData),
 }
 
+/// Quiet move, or a `Promotion` when `promotion` is given. The kind is not
+/// checked against a board, so a pawn move two rows forward is not a double
+/// push and `Board::make_move` panics on it; `Move::from_uci` and
+/// `Move::from_san` find the kind from the board.
 pub fn mv(from: Field, to: Field, promotion: Option<Piece>) -> Move {
-    Move::Move(MoveData {
+    let d = MoveData {
         from,
         to,
         promotion,
-    })
+    };
+    match promotion {
+        Some(_) => Move::Promotion(d),
+        None => Move::Move(d),
+    }
 }
 
+/// Capture, or a `PromotionCapture` when `promotion` is given. An en-passant
+/// capture needs `en_passant` instead.
 pub fn capture(from: Field, to: Field, promotion: Option<Piece>) -> Move {
-    Move::Capture(MoveData {
+    let d = MoveData {
         from,
         to,
         promotion,
+    };
+    match promotion {
+        Some(_) => Move::PromotionCapture(d),
+        None => Move::Capture(d),
+    }
+}
+
+pub fn en_passant(from: Field, to: Field) -> Move {
+    Move::EnPassant(MoveData {
+        from,
+        to,
+        promotion: None,
     })
 }
 
+pub fn double_push(from: Field, to: Field) -> Move {
+    Move::DoublePush(MoveData {
+        from,
+        to,
+        promotion: None,
+    })
+}
+
+/// Castling of the king on `king_from` with the rook on `rook_from`. As in
+/// Chess960 the king ends on the g or c file and the rook next to it.
+pub fn castle(king_from: Field, rook_from: Field) -> Move {
+    let row = king_from.row();
+    if rook_from.column() > king_from.column() {
+        Move::KingCastle(CastleData {
+            king_from,
+            king_to: Field::new(row, 7),
+            rook_from,
+            rook_to: Field::new(row, 6),
+        })
+    } else {
+        Move::QueenCastle(CastleData {
+            king_from,
+            king_to: Field::new(row, 3),
+            rook_from,
+            rook_to: Field::new(row, 4),
+        })
+    }
+}
+
+pub fn king_castle(side: Side) -> Move {
+    match side {
+        Side::White => castle(E1, H1),
+        Side::Black => castle(E8, H8),
+    }
+}
+
+pub fn queen_castle(side: Side) -> Move {
+    match side {
+        Side::White => castle(E1, A1),
+        Side::Black => castle(E8, A8),
+    }
+}
+
+impl Move {
+    /// Returns from, to and promotion of any move but castling
+    pub fn move_data(&self) -> Option<MoveData> {
+        match self {
+            Move::Move(d)
+            | Move::Capture(d)
+            | Move::EnPassant(d)
+            | Move::DoublePush(d)
+            | Move::Promotion(d)
+            | Move::PromotionCapture(d) => Some(*d),
+            Move::KingCastle(_) | Move::QueenCastle(_) => None,
+        }
+    }
+
+    pub fn is_capture(&self) -> bool {
+        matches!(
+            self,
+            Move::Capture(_) | Move::EnPassant(_) | Move::PromotionCapture(_)
+        )
+    }
+
+    pub fn is_en_passant(&self) -> bool {
+        matches!(self, Move::EnPassant(_))
+    }
+
+    pub fn is_double_push(&self) -> bool {
+        matches!(self, Move::DoublePush(_))
+    }
+
+    pub fn is_castle(&self) -> bool {
+        matches!(self, Move::KingCastle(_) | Move::QueenCastle(_))
+    }
+
+    pub fn promotion(&self) -> Option<Piece> {
+        self.move_data().and_then(|d| d.promotion)
+    }
+
+    /// Returns the field of the captured piece, which is off the target
+    /// field for en-passant captures
+    pub fn captured_square(&self) -> Option<Field> {
+        match self {
+            Move::Capture(d) | Move::PromotionCapture(d) => Some(d.to),
+            Move::EnPassant(d) => Some(Field::new(d.from.row(), d.to.column())),
+            _ => None,
+        }
+    }
+
+    /// Returns king-from, king-to, rook-from and rook-to fields of a castling move
+    pub fn castle_fields(&self) -> Option<(Field, Field, Field, Field)> {
+        match self {
+            Move::KingCastle(c) | Move::QueenCastle(c) => {
+                Some((c.king_from, c.king_to, c.rook_from, c.rook_to))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the side a castling move belongs to
+    pub fn castle_side(&self) -> Option<Side> {
+        self.castle_fields().map(|(king_from, _, _, _)| {
+            if king_from.row() == 1 {
+                Side::White
+            } else {
+                Side::Black
+            }
+        })
+    }
+}
+
 impl fmt::Display for Move {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let capture_str = |c: Option<Piece>| match c {
@@ -41,50 +222,50 @@ impl fmt::Display for Move {
             None => "".to_string(),
         };
         match self {
-            Move::Capture(m) => write!(f, "{}x{}{}", m.from, m.to, capture_str(m.promotion)),
-            Move::Move(m) => write!(f, "{}{}{}", m.from, m.to, capture_str(m.promotion)),
-            Move::KingCastle => write!(f, "O-O"),
-            Move::QueenCastle => write!(f, "O-O-O"),
+            Move::Capture(m) | Move::EnPassant(m) | Move::PromotionCapture(m) => {
+                write!(f, "{}x{}{}", m.from, m.to, capture_str(m.promotion))
+            }
+            Move::Move(m) | Move::DoublePush(m) | Move::Promotion(m) => {
+                write!(f, "{}{}{}", m.from, m.to, capture_str(m.promotion))
+            }
+            Move::KingCastle(_) => write!(f, "O-O"),
+            Move::QueenCastle(_) => write!(f, "O-O-O"),
         }
     }
 }
 
+/// Parses the `Display` form of a move without a board, with the same
+/// caveats as `mv` and `capture`
 impl FromStr for Move {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Without a board castling is taken to be White's with the king and
+        // rooks on their standard fields, Move::from_san resolves the side
         if s == "O-O" {
-            Ok(Move::KingCastle)
+            Ok(king_castle(Side::White))
         } else if s == "O-O-O" {
-            Ok(Move::QueenCastle)
+            Ok(queen_castle(Side::White))
         } else if s.len() == 4 {
-            let from = s.get(0..2).ok_or_else(|| ())?.parse::<Field>()?;
-            let to = s.get(2..4).ok_or_else(|| ())?.parse::<Field>()?;
+            let from = s.get(0..2).ok_or(())?.parse::<Field>()?;
+            let to = s.get(2..4).ok_or(())?.parse::<Field>()?;
             Ok(mv(from, to, None))
-        } else if s.len() == 5 && Ok('x') == s.get(2..3).ok_or_else(|| ())?.parse::<char>() {
-            let from = s.get(0..2).ok_or_else(|| ())?.parse::<Field>()?;
-            let to = s.get(3..5).ok_or_else(|| ())?.parse::<Field>()?;
+        } else if s.len() == 5 && Ok('x') == s.get(2..3).ok_or(())?.parse::<char>() {
+            let from = s.get(0..2).ok_or(())?.parse::<Field>()?;
+            let to = s.get(3..5).ok_or(())?.parse::<Field>()?;
             Ok(capture(from, to, None))
-        } else if s.len() == 6 && Ok('=') == s.get(4..5).ok_or_else(|| ())?.parse::<char>() {
-            let from = s.get(0..2).ok_or_else(|| ())?.parse::<Field>()?;
-            let to = s.get(2..4).ok_or_else(|| ())?.parse::<Field>()?;
-            let piece = s
-                .get(5..6)
-                .ok_or_else(|| ())?
-                .parse::<Piece>()
-                .map_err(|_| ())?;
+        } else if s.len() == 6 && Ok('=') == s.get(4..5).ok_or(())?.parse::<char>() {
+            let from = s.get(0..2).ok_or(())?.parse::<Field>()?;
+            let to = s.get(2..4).ok_or(())?.parse::<Field>()?;
+            let piece = s.get(5..6).ok_or(())?.parse::<Piece>().map_err(|_| ())?;
             Ok(mv(from, to, Some(piece)))
         } else if s.len() == 7
-            && Ok('x') == s.get(2..3).ok_or_else(|| ())?.parse::<char>()
-            && Ok('=') == s.get(5..6).ok_or_else(|| ())?.parse::<char>()
+            && Ok('x') == s.get(2..3).ok_or(())?.parse::<char>()
+            && Ok('=') == s.get(5..6).ok_or(())?.parse::<char>()
         {
-            let from = s.get(0..2).ok_or_else(|| ())?.parse::<Field>()?;
-            let to = s.get(3..5).ok_or_else(|| ())?.parse::<Field>()?;
-            let piece = s
-                .get(6..7)
-                .ok_or_else(|| ())?
-                .parse::<Piece>()
-                .map_err(|_| ())?;
+            let from = s.get(0..2).ok_or(())?.parse::<Field>()?;
+            let to = s.get(3..5).ok_or(())?.parse::<Field>()?;
+            let piece = s.get(6..7).ok_or(())?.parse::<Piece>().map_err(|_| ())?;
             Ok(capture(from, to, Some(piece)))
         } else {
             Err(())
@@ -92,10 +273,144 @@ impl FromStr for Move {
     }
 }
 
+/// Move packed into 16 bits: 6 bits from field, 6 bits to field and 4 bits
+/// of flags. Castling moves store the king and rook fields.
+#[derive(Debug, Eq, PartialEq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub struct PackedMove(u16);
+
+const QUIET: u16 = 0;
+const DOUBLE_PUSH: u16 = 1;
+const KING_CASTLE: u16 = 2;
+const QUEEN_CASTLE: u16 = 3;
+const CAPTURE: u16 = 4;
+const EN_PASSANT: u16 = 5;
+// Set for promotions, the lower two bits select the piece
+const PROMOTION: u16 = 8;
+const PROMOTION_PIECES: [Piece; 4] = [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen];
+
+impl PackedMove {
+    /// Named apart from `MoveData::from` so it is not mistaken for a conversion
+    pub fn from_field(&self) -> Field {
+        Field((self.0 & 0x3f) as usize)
+    }
+
+    pub fn to_field(&self) -> Field {
+        Field(((self.0 >> 6) & 0x3f) as usize)
+    }
+
+    fn flags(&self) -> u16 {
+        self.0 >> 12
+    }
+
+    pub fn promotion(&self) -> Option<Piece> {
+        if self.flags() & PROMOTION != 0 {
+            Some(PROMOTION_PIECES[(self.flags() & 3) as usize])
+        } else {
+            None
+        }
+    }
+
+    pub fn is_capture(&self) -> bool {
+        self.flags() & CAPTURE != 0
+    }
+
+    pub fn is_en_passant(&self) -> bool {
+        self.flags() == EN_PASSANT
+    }
+
+    pub fn is_double_push(&self) -> bool {
+        self.flags() == DOUBLE_PUSH
+    }
+
+    pub fn is_castle(&self) -> bool {
+        self.flags() == KING_CASTLE || self.flags() == QUEEN_CASTLE
+    }
+}
+
+impl TryFrom<Move> for PackedMove {
+    type Error = &'static str;
+
+    fn try_from(m: Move) -> Result<Self, Self::Error> {
+        let pack = |d: MoveData, flags: u16| {
+            let flags = match d.promotion {
+                Some(p) => {
+                    let idx = PROMOTION_PIECES
+                        .iter()
+                        .position(|x| *x == p)
+                        .ok_or("Promotion to a king or pawn")?;
+                    flags | PROMOTION | idx as u16
+                }
+                None => flags,
+            };
+            Ok(PackedMove(
+                d.from.0 as u16 | (d.to.0 as u16) << 6 | flags << 12,
+            ))
+        };
+        match m {
+            Move::Move(d) | Move::Promotion(d) => pack(d, QUIET),
+            Move::Capture(d) | Move::PromotionCapture(d) => pack(d, CAPTURE),
+            Move::EnPassant(d) => pack(d, EN_PASSANT),
+            Move::DoublePush(d) => pack(d, DOUBLE_PUSH),
+            Move::KingCastle(c) => Ok(PackedMove(pack_castle(c, KING_CASTLE))),
+            Move::QueenCastle(c) => Ok(PackedMove(pack_castle(c, QUEEN_CASTLE))),
+        }
+    }
+}
+
+fn pack_castle(c: CastleData, flags: u16) -> u16 {
+    c.king_from.0 as u16 | (c.rook_from.0 as u16) << 6 | flags << 12
+}
+
+impl From<PackedMove> for Move {
+    fn from(m: PackedMove) -> Self {
+        match m.flags() {
+            KING_CASTLE | QUEEN_CASTLE => castle(m.from_field(), m.to_field()),
+            EN_PASSANT => en_passant(m.from_field(), m.to_field()),
+            DOUBLE_PUSH => double_push(m.from_field(), m.to_field()),
+            _ if m.is_capture() => capture(m.from_field(), m.to_field(), m.promotion()),
+            _ => mv(m.from_field(), m.to_field(), m.promotion()),
+        }
+    }
+}
+
+impl From<PackedMove> for u16 {
+    fn from(m: PackedMove) -> Self {
+        m.0
+    }
+}
+
+impl TryFrom<u16> for PackedMove {
+    type Error = &'static str;
+
+    fn try_from(bits: u16) -> Result<Self, Self::Error> {
+        let m = PackedMove(bits);
+        match m.flags() {
+            6 | 7 => Err("Unused move flags"),
+            KING_CASTLE | QUEEN_CASTLE => {
+                let (king, rook) = (m.from_field(), m.to_field());
+                let king_side = rook.column() > king.column();
+                if king.row() != rook.row() || king == rook {
+                    Err("Castling rook not on the king's rank")
+                } else if king_side != (m.flags() == KING_CASTLE) {
+                    Err("Castling rook on the wrong side")
+                } else {
+                    Ok(m)
+                }
+            }
+            _ => Ok(m),
+        }
+    }
+}
+
+impl fmt::Display for PackedMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Move::from(*self))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::field::named::*;
 
     #[test]
     fn to_string() {
@@ -104,8 +419,8 @@ mod tests {
         assert_eq!(capture(E2, E4, None).to_string(), "e2xe4");
         assert_eq!(mv(A7, A8, Some(Piece::Queen)).to_string(), "a7a8=Q");
         assert_eq!(capture(C7, B8, Some(Piece::Knight)).to_string(), "c7xb8=N");
-        assert_eq!(Move::KingCastle.to_string(), "O-O");
-        assert_eq!(Move::QueenCastle.to_string(), "O-O-O");
+        assert_eq!(king_castle(Side::White).to_string(), "O-O");
+        assert_eq!(queen_castle(Side::Black).to_string(), "O-O-O");
     }
 
     #[test]
@@ -118,8 +433,8 @@ mod tests {
             Ok(capture(C7, B8, Some(Piece::Rook))),
             "c7xb8=R".parse::<Move>()
         );
-        assert_eq!(Ok(Move::KingCastle), "O-O".parse::<Move>());
-        assert_eq!(Ok(Move::QueenCastle), "O-O-O".parse::<Move>());
+        assert_eq!(Ok(king_castle(Side::White)), "O-O".parse::<Move>());
+        assert_eq!(Ok(queen_castle(Side::White)), "O-O-O".parse::<Move>());
         assert!("e2e4uv".parse::<Move>().is_err());
         assert!("e2e4u".parse::<Move>().is_err());
         assert!("e2e".parse::<Move>().is_err());
@@ -127,4 +442,147 @@ mod tests {
         assert!("e".parse::<Move>().is_err());
         assert!("".parse::<Move>().is_err());
     }
+
+    #[test]
+    fn packed() {
+        assert_eq!(std::mem::size_of::<PackedMove>(), 2);
+        let moves = [
+            mv(A1, B2, None),
+            mv(H8, A1, None),
+            capture(D1, H7, None),
+            mv(A7, A8, Some(Piece::Queen)),
+            mv(H2, H1, Some(Piece::Knight)),
+            capture(C7, B8, Some(Piece::Rook)),
+            capture(C2, B1, Some(Piece::Bishop)),
+            king_castle(Side::White),
+            queen_castle(Side::White),
+            king_castle(Side::Black),
+            queen_castle(Side::Black),
+            castle(B1, G1),
+            castle(G8, C8),
+            en_passant(E5, D6),
+            en_passant(B4, C3),
+            double_push(E2, E4),
+            double_push(H7, H5),
+        ];
+        for m in &moves {
+            let packed = PackedMove::try_from(*m).unwrap();
+            assert_eq!(Move::from(packed), *m);
+            assert_eq!(packed.to_string(), m.to_string());
+            let bits = u16::from(packed);
+            assert_eq!(PackedMove::try_from(bits), Ok(packed));
+        }
+    }
+
+    #[test]
+    fn packed_accessors() {
+        let m = PackedMove::try_from(capture(C7, B8, Some(Piece::Rook))).unwrap();
+        assert_eq!(m.from_field(), C7);
+        assert_eq!(m.to_field(), B8);
+        assert_eq!(m.promotion(), Some(Piece::Rook));
+        assert!(m.is_capture());
+        assert!(!m.is_castle());
+        let m = PackedMove::try_from(mv(E2, E4, None)).unwrap();
+        assert_eq!(
+            (m.from_field(), m.to_field(), m.promotion()),
+            (E2, E4, None)
+        );
+        assert!(!m.is_capture());
+        assert!(!m.is_en_passant());
+        assert_eq!(u16::from(m), 12 | 28 << 6);
+        assert!(PackedMove::try_from(queen_castle(Side::White))
+            .unwrap()
+            .is_castle());
+        assert!(PackedMove::try_from(mv(E7, E8, Some(Piece::King))).is_err());
+        assert!(PackedMove::try_from(capture(E7, D8, Some(Piece::Pawn))).is_err());
+    }
+
+    #[test]
+    fn packed_bits() {
+        let m = PackedMove::try_from(12 | 28 << 6 | 1 << 12).unwrap();
+        assert!(m.is_double_push());
+        assert_eq!(Move::from(m), double_push(E2, E4));
+        let m = PackedMove::try_from(36 | 43 << 6 | 5 << 12).unwrap();
+        assert!(m.is_en_passant());
+        assert!(m.is_capture());
+        assert_eq!(Move::from(m), en_passant(E5, D6));
+        assert!(PackedMove::try_from(6 << 12).is_err());
+        assert!(PackedMove::try_from(7 << 12).is_err());
+        assert!(PackedMove::try_from(2 << 12 | 4).is_err());
+        assert!(PackedMove::try_from(3 << 12 | 4 | 56 << 6).is_err());
+        assert_eq!(
+            PackedMove::try_from(3 << 12 | 4).map(Move::from),
+            Ok(queen_castle(Side::White))
+        );
+    }
+
+    #[test]
+    fn castling() {
+        assert_eq!(
+            king_castle(Side::White).castle_fields(),
+            Some((E1, G1, H1, F1))
+        );
+        assert_eq!(
+            queen_castle(Side::White).castle_fields(),
+            Some((E1, C1, A1, D1))
+        );
+        assert_eq!(
+            king_castle(Side::Black).castle_fields(),
+            Some((E8, G8, H8, F8))
+        );
+        assert_eq!(
+            queen_castle(Side::Black).castle_fields(),
+            Some((E8, C8, A8, D8))
+        );
+        assert_eq!(queen_castle(Side::Black).castle_side(), Some(Side::Black));
+        assert_eq!(mv(E1, G1, None).castle_fields(), None);
+        assert_eq!(mv(E1, G1, None).castle_side(), None);
+        // Chess960 king on b1 and rooks on a1 and f1
+        assert_eq!(castle(B1, F1).castle_fields(), Some((B1, G1, F1, F1)));
+        assert_eq!(castle(B1, A1).castle_fields(), Some((B1, C1, A1, D1)));
+        assert_eq!(castle(B1, F1).to_string(), "O-O");
+    }
+
+    #[test]
+    fn kinds() {
+        assert!(matches!(mv(A7, A8, Some(Piece::Queen)), Move::Promotion(_)));
+        assert!(matches!(
+            capture(A7, B8, Some(Piece::Rook)),
+            Move::PromotionCapture(_)
+        ));
+        let moves = [
+            (mv(E2, E3, None), false, None, None),
+            (double_push(E2, E4), false, None, None),
+            (capture(D1, H7, None), true, Some(H7), None),
+            (en_passant(E5, D6), true, Some(D5), None),
+            (en_passant(B4, C3), true, Some(C4), None),
+            (
+                mv(A7, A8, Some(Piece::Queen)),
+                false,
+                None,
+                Some(Piece::Queen),
+            ),
+            (
+                capture(A2, B1, Some(Piece::Knight)),
+                true,
+                Some(B1),
+                Some(Piece::Knight),
+            ),
+            (king_castle(Side::White), false, None, None),
+        ];
+        for (m, is_capture, captured, promotion) in &moves {
+            assert_eq!(m.is_capture(), *is_capture, "{}", m);
+            assert_eq!(m.captured_square(), *captured, "{}", m);
+            assert_eq!(m.promotion(), *promotion, "{}", m);
+        }
+        assert!(en_passant(E5, D6).is_en_passant());
+        assert!(!capture(E5, D6, None).is_en_passant());
+        assert!(double_push(E2, E4).is_double_push());
+        assert!(!mv(E2, E4, None).is_double_push());
+        assert!(queen_castle(Side::Black).is_castle());
+        assert_eq!(en_passant(E5, D6).to_string(), "e5xd6");
+        assert_eq!(double_push(E2, E4).to_string(), "e2e4");
+        assert_eq!(king_castle(Side::White).move_data(), None);
+        assert_eq!(double_push(E2, E4).move_data().map(|d| d.to()), Some(E4));
+    }
 }
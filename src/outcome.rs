@@ -0,0 +1,119 @@
+use crate::piece::Side;
+use std::fmt;
+use std::str::FromStr;
+
+/// Result of a game as written in PGN
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    /// Game in progress, abandoned or result unknown
+    Unknown,
+}
+
+impl GameResult {
+    /// Returns the winner, if any
+    pub fn winner(&self) -> Option<Side> {
+        match self {
+            GameResult::WhiteWins => Some(Side::White),
+            GameResult::BlackWins => Some(Side::Black),
+            GameResult::Draw | GameResult::Unknown => None,
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Unknown => "*",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for GameResult {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1-0" => Ok(GameResult::WhiteWins),
+            "0-1" => Ok(GameResult::BlackWins),
+            "1/2-1/2" => Ok(GameResult::Draw),
+            "*" => Ok(GameResult::Unknown),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Why a game ended
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Termination {
+    Checkmate,
+    Stalemate,
+    /// Draw claimed by a player or agreed by both
+    DrawClaimed,
+    /// Draw claimed after the position occurred three times
+    ThreefoldRepetition,
+    /// Position occurred five times
+    FivefoldRepetition,
+    /// Draw claimed after fifty moves by each side without a capture or pawn
+    /// move
+    FiftyMoves,
+    /// Seventy-five moves by each side without a capture or pawn move
+    SeventyFiveMoves,
+    Resignation,
+    /// Loss on time, or a draw when the opponent has no mating material
+    Timeout,
+    /// Neither side can mate
+    InsufficientMaterial,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Outcome {
+    pub result: GameResult,
+    pub termination: Termination,
+}
+
+impl Outcome {
+    /// Outcome of a game won by `side`
+    pub fn win(side: Side, termination: Termination) -> Self {
+        let result = match side {
+            Side::White => GameResult::WhiteWins,
+            Side::Black => GameResult::BlackWins,
+        };
+        Outcome {
+            result,
+            termination,
+        }
+    }
+
+    pub fn draw(termination: Termination) -> Self {
+        Outcome {
+            result: GameResult::Draw,
+            termination,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_display() {
+        for s in &["1-0", "0-1", "1/2-1/2", "*"] {
+            assert_eq!(s.parse::<GameResult>().unwrap().to_string(), *s);
+        }
+        assert!("½-½".parse::<GameResult>().is_err());
+        assert!("".parse::<GameResult>().is_err());
+        assert_eq!(GameResult::BlackWins.winner(), Some(Side::Black));
+        assert_eq!(GameResult::Draw.winner(), None);
+        let outcome = Outcome::win(Side::Black, Termination::Timeout);
+        assert_eq!(outcome.result, GameResult::BlackWins);
+        assert_eq!(Outcome::draw(Termination::Stalemate).result.winner(), None);
+    }
+}
@@ -0,0 +1,217 @@
+use crate::board::Board;
+use crate::move_generator::{generate, in_check};
+use crate::mv::Move;
+
+/// Counts leaf nodes of the legal move tree of the given depth
+pub fn perft(b: &Board, depth: u32) -> u64 {
+    let mut b = b.clone();
+    count(&mut b, depth)
+}
+
+/// Splits perft count by legal moves of the root position
+pub fn divide(b: &Board, depth: u32) -> Vec<(Move, u64)> {
+    let mut b = b.clone();
+    let side = b.active;
+    let mut rv = Vec::new();
+    for m in generate(&b) {
+        let undo = b.make_move(m);
+        if !in_check(&b, side) {
+            let nodes = if depth > 0 {
+                count(&mut b, depth - 1)
+            } else {
+                0
+            };
+            rv.push((m, nodes));
+        }
+        b.unmake_move(m, undo);
+    }
+    rv
+}
+
+fn count(b: &mut Board, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let side = b.active;
+    let mut nodes = 0;
+    for m in generate(b) {
+        let undo = b.make_move(m);
+        if !in_check(b, side) {
+            nodes += if depth == 1 { 1 } else { count(b, depth - 1) };
+        }
+        b.unmake_move(m, undo);
+    }
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::from_fen;
+    use crate::field::named::*;
+    use crate::mv::double_push;
+
+    fn check(fen: &str, expected: &[u64]) {
+        let b = from_fen(fen).unwrap();
+        for (depth, nodes) in expected.iter().enumerate() {
+            assert_eq!(
+                perft(&b, depth as u32 + 1),
+                *nodes,
+                "Depth {} of {}",
+                depth + 1,
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn initial() {
+        check(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            &[20, 400, 8902, 197281],
+        );
+    }
+
+    #[test]
+    fn kiwipete() {
+        check(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            &[48, 2039, 97862],
+        );
+    }
+
+    #[test]
+    fn position3() {
+        check(
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            &[14, 191, 2812, 43238],
+        );
+    }
+
+    #[test]
+    fn position4() {
+        check(
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            &[6, 264, 9467],
+        );
+        check(
+            "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+            &[6, 264, 9467],
+        );
+    }
+
+    #[test]
+    fn position5() {
+        check(
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            &[44, 1486, 62379],
+        );
+    }
+
+    #[test]
+    fn position6() {
+        check(
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            &[46, 2079, 89890],
+        );
+    }
+
+    #[test]
+    fn edge_cases() {
+        // Illegal en-passant captures exposing the king
+        check("3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1", &[18, 92, 1670, 10138]);
+        check(
+            "8/8/4k3/8/2p5/8/B2P2K1/8 w - - 0 1",
+            &[13, 102, 1266, 10276],
+        );
+        // En-passant capture giving check
+        check(
+            "8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1",
+            &[15, 126, 1928, 13931],
+        );
+        // Castling giving check
+        check("5k2/8/8/8/8/8/8/4K2R w K - 0 1", &[15, 66, 1198, 6399]);
+        check("3k4/8/8/8/8/8/8/R3K3 w Q - 0 1", &[16, 71, 1286, 7418]);
+        // Castling rights lost by captures and prevented by attacks
+        check(
+            "r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1",
+            &[26, 1141, 27826],
+        );
+        check(
+            "r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1",
+            &[44, 1494, 50509],
+        );
+        // Promotions out of and into check
+        check("2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1", &[11, 133, 1442, 19174]);
+        check("4k3/1P6/8/8/8/8/K7/8 w - - 0 1", &[9, 40, 472, 2661, 38983]);
+        check("8/P1k5/K7/8/8/8/8/8 w - - 0 1", &[6, 27, 273, 1329, 18135]);
+        // Discovered check
+        check(
+            "8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1",
+            &[29, 165, 5160, 31961],
+        );
+        // Stalemate and checkmate
+        check("K1k5/8/P7/8/8/8/8/8 w - - 0 1", &[2, 6, 13, 63, 382, 2217]);
+        check("8/k1P5/8/1K6/8/8/8/8 w - - 0 1", &[10, 25, 268, 926, 10857]);
+        check("8/8/2k5/5q2/5n2/8/5K2/8 b - - 0 1", &[37, 183, 6559, 23527]);
+    }
+
+    /// Full depth counts, run with `cargo test --release -- --ignored`
+    #[test]
+    #[ignore]
+    fn deep() {
+        let suite: &[(&str, u32, u64)] = &[
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                5,
+                4865609,
+            ),
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                4,
+                4085603,
+            ),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 5, 674624),
+            (
+                "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                4,
+                422333,
+            ),
+            (
+                "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+                4,
+                2103487,
+            ),
+            (
+                "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+                4,
+                3894594,
+            ),
+            ("3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1", 6, 1134888),
+            ("8/8/4k3/8/2p5/8/B2P2K1/8 w - - 0 1", 6, 1015133),
+            ("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1", 6, 1440467),
+            ("5k2/8/8/8/8/8/8/4K2R w K - 0 1", 6, 661072),
+            ("3k4/8/8/8/8/8/8/R3K3 w Q - 0 1", 6, 803711),
+            ("r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1", 4, 1274206),
+            ("r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1", 4, 1720476),
+            ("2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1", 6, 3821001),
+            ("8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1", 5, 1004658),
+            ("4k3/1P6/8/8/8/8/K7/8 w - - 0 1", 6, 217342),
+            ("8/P1k5/K7/8/8/8/8/8 w - - 0 1", 6, 92683),
+            ("8/k1P5/8/1K6/8/8/8/8 w - - 0 1", 7, 567584),
+        ];
+        for (fen, depth, nodes) in suite {
+            let b = from_fen(fen).unwrap();
+            assert_eq!(perft(&b, *depth), *nodes, "Depth {} of {}", depth, fen);
+        }
+    }
+
+    #[test]
+    fn divide_initial() {
+        let b = Board::initial();
+        let moves = divide(&b, 3);
+        assert_eq!(moves.len(), 20);
+        assert_eq!(moves.iter().map(|(_, n)| n).sum::<u64>(), 8902);
+        assert!(moves.contains(&(double_push(E2, E4), 600)));
+    }
+}
@@ -0,0 +1,1396 @@
+use crate::board::Board;
+use crate::fen::{from_fen, to_fen, FenError};
+use crate::mv::Move;
+use crate::outcome::GameResult;
+use crate::piece::Side;
+use crate::san::SanError;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::iter::Peekable;
+use std::mem;
+use std::path::Path;
+use std::str::{Chars, FromStr};
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub enum PgnError {
+    /// Malformed text on a line
+    Syntax { line: usize, message: String },
+    /// Position of the FEN tag cannot be parsed
+    Fen(FenError),
+    /// Move which cannot be played, `ply` counts half-moves from the start
+    Move { ply: usize, error: SanError },
+    /// Text without any game
+    Empty,
+    /// Failure to read the input of a `PgnReader`, shared so errors can be
+    /// cloned
+    Io(Arc<io::Error>),
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PgnError::Syntax { line, message } => write!(f, "Line {}: {}", line, message),
+            PgnError::Fen(e) => write!(f, "Invalid FEN tag: {}", e),
+            PgnError::Move { ply, error } => write!(f, "Ply {}: {}", ply, error),
+            PgnError::Empty => write!(f, "No game found"),
+            PgnError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+// io::Error cannot be compared, read errors are equal when their kinds are
+impl PartialEq for PgnError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                PgnError::Syntax { line, message },
+                PgnError::Syntax {
+                    line: other_line,
+                    message: other_message,
+                },
+            ) => line == other_line && message == other_message,
+            (PgnError::Fen(e), PgnError::Fen(other)) => e == other,
+            (
+                PgnError::Move { ply, error },
+                PgnError::Move {
+                    ply: other_ply,
+                    error: other_error,
+                },
+            ) => ply == other_ply && error == other_error,
+            (PgnError::Empty, PgnError::Empty) => true,
+            (PgnError::Io(e), PgnError::Io(other)) => e.kind() == other.kind(),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for PgnError {}
+
+impl Error for PgnError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PgnError::Io(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// Move of a game with its annotations
+#[derive(Debug, PartialEq, Clone)]
+pub struct PgnMove {
+    pub mv: Move,
+    /// Numeric annotation glyphs, `!` and `?` suffixes are stored as 1 to 6
+    pub nags: Vec<u8>,
+    /// Comments before the move, only at the start of a game or variation
+    pub comments_before: Vec<String>,
+    pub comments: Vec<String>,
+    /// Alternatives to this move, each a line of moves
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+impl PgnMove {
+    pub fn new(mv: Move) -> Self {
+        PgnMove {
+            mv,
+            nags: Vec::new(),
+            comments_before: Vec::new(),
+            comments: Vec::new(),
+            variations: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PgnGame {
+    /// Tags in the order of the text
+    pub tags: Vec<(String, String)>,
+    /// Initial position, from the FEN tag when present
+    pub start: Board,
+    pub moves: Vec<PgnMove>,
+    pub result: GameResult,
+    /// Comments of a game without moves
+    pub comments: Vec<String>,
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns the moves of the main line
+    pub fn mainline(&self) -> Vec<Move> {
+        self.moves.iter().map(|m| m.mv).collect()
+    }
+
+    /// Returns the position after the main line
+    pub fn end(&self) -> Board {
+        let mut b = self.start.clone();
+        for m in &self.moves {
+            b.make_move(m.mv);
+        }
+        b
+    }
+}
+
+impl FromStr for PgnGame {
+    type Err = PgnError;
+
+    /// Parses the first game of the text
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Lexer::new(s).peekable();
+        parse_game(&mut tokens)?.ok_or(PgnError::Empty)
+    }
+}
+
+/// Parses all games of a PGN text
+pub fn from_pgn(s: &str) -> Result<Vec<PgnGame>, PgnError> {
+    let mut tokens = Lexer::new(s).peekable();
+    let mut rv = Vec::new();
+    while let Some(game) = parse_game(&mut tokens)? {
+        rv.push(game);
+    }
+    Ok(rv)
+}
+
+/// What `PgnGame::to_pgn` writes besides tags and moves
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct PgnOptions {
+    pub comments: bool,
+    pub nags: bool,
+    pub variations: bool,
+    /// Clock commands like `[%clk 0:05:00]` inside comments, kept even when
+    /// other comment text is stripped
+    pub clocks: bool,
+    /// `SetUp` and `FEN` tags for games not starting from the initial position
+    pub fen: bool,
+}
+
+impl Default for PgnOptions {
+    fn default() -> Self {
+        PgnOptions {
+            comments: true,
+            nags: true,
+            variations: true,
+            clocks: true,
+            fen: true,
+        }
+    }
+}
+
+const ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+const LINE_LENGTH: usize = 80;
+
+impl PgnGame {
+    /// Writes the game in PGN export format: the seven tag roster first,
+    /// other tags sorted by name, movetext wrapped at 80 columns
+    pub fn to_pgn(&self, options: &PgnOptions) -> String {
+        let result = self.result.to_string();
+        let mut rv = String::new();
+        for (name, default) in &ROSTER {
+            let value = match *name {
+                "Result" => Some(result.as_str()),
+                _ => self.tag(name),
+            };
+            rv.push_str(&tag_line(name, value.unwrap_or(default)));
+        }
+        let fen = if options.fen && self.start != Board::initial() {
+            Some(to_fen(&self.start))
+        } else {
+            None
+        };
+        let mut tags: Vec<(&str, &str)> = self
+            .tags
+            .iter()
+            .filter(|(n, _)| !ROSTER.iter().any(|(r, _)| r == n) && n != "SetUp" && n != "FEN")
+            .map(|(n, v)| (n.as_str(), v.as_str()))
+            .collect();
+        if let Some(fen) = &fen {
+            tags.push(("SetUp", "1"));
+            tags.push(("FEN", fen));
+        }
+        tags.sort_by_key(|(n, _)| *n);
+        for (name, value) in tags {
+            rv.push_str(&tag_line(name, value));
+        }
+        rv.push('\n');
+
+        let mut words = Vec::new();
+        for c in &self.comments {
+            push_comment(&mut words, c, options);
+        }
+        push_line(&mut words, &self.moves, &self.start, options);
+        words.push(result);
+        let mut line = String::new();
+        for word in words {
+            if !line.is_empty() && line.len() + 1 + word.len() > LINE_LENGTH {
+                rv.push_str(&line);
+                rv.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&word);
+        }
+        rv.push_str(&line);
+        rv.push('\n');
+        rv
+    }
+}
+
+impl fmt::Display for PgnGame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_pgn(&PgnOptions::default()))
+    }
+}
+
+/// Writes games separated by empty lines
+pub fn to_pgn(games: &[PgnGame], options: &PgnOptions) -> String {
+    games
+        .iter()
+        .map(|g| g.to_pgn(options))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn tag_line(name: &str, value: &str) -> String {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("[{} \"{}\"]\n", name, value)
+}
+
+/// Adds the words of a comment, keeping only the parts the options allow
+fn push_comment(words: &mut Vec<String>, comment: &str, options: &PgnOptions) -> bool {
+    let mut kept = Vec::new();
+    let mut rest = comment;
+    while let Some(start) = rest.find("[%clk") {
+        let end = match rest[start..].find(']') {
+            Some(end) => start + end + 1,
+            None => break,
+        };
+        if options.comments {
+            kept.push(&rest[..start]);
+        }
+        if options.clocks {
+            kept.push(&rest[start..end]);
+        }
+        rest = &rest[end..];
+    }
+    if options.comments {
+        kept.push(rest);
+    }
+    let text: Vec<&str> = kept
+        .iter()
+        .flat_map(|s| s.split_whitespace())
+        .filter(|w| !w.contains('}'))
+        .collect();
+    if text.is_empty() {
+        return false;
+    }
+    let last = text.len() - 1;
+    for (idx, word) in text.iter().enumerate() {
+        let mut word = word.to_string();
+        if idx == 0 {
+            word.insert(0, '{');
+        }
+        if idx == last {
+            word.push('}');
+        }
+        words.push(word);
+    }
+    true
+}
+
+fn push_line(words: &mut Vec<String>, moves: &[PgnMove], start: &Board, options: &PgnOptions) {
+    let mut b = start.clone();
+    let mut numbered = false;
+    for m in moves {
+        for c in &m.comments_before {
+            numbered &= !push_comment(words, c, options);
+        }
+        let san = m.mv.to_san(&b);
+        match b.active {
+            Side::White => words.push(format!("{}. {}", b.full_moves, san)),
+            Side::Black if numbered => words.push(san),
+            Side::Black => words.push(format!("{}... {}", b.full_moves, san)),
+        }
+        numbered = true;
+        if options.nags {
+            words.extend(m.nags.iter().map(|n| format!("${}", n)));
+        }
+        for c in &m.comments {
+            numbered &= !push_comment(words, c, options);
+        }
+        if options.variations {
+            for variation in m.variations.iter().filter(|v| !v.is_empty()) {
+                let first = words.len();
+                push_line(words, variation, &b, options);
+                words[first].insert(0, '(');
+                if let Some(last) = words.last_mut() {
+                    last.push(')');
+                }
+                numbered = false;
+            }
+        }
+        b.make_move(m.mv);
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Open,
+    Close,
+    Nag(u8),
+    San(String),
+    Result(GameResult),
+}
+
+struct Lexer<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    line_start: bool,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(s: &'a str) -> Self {
+        // Editors on Windows like to start files with a byte order mark
+        let s = s.strip_prefix('\u{feff}').unwrap_or(s);
+        Lexer {
+            chars: s.chars().peekable(),
+            line: 1,
+            line_start: true,
+        }
+    }
+
+    /// Counts lines from `line` instead of 1
+    fn starting_at(mut self, line: usize) -> Self {
+        self.line = line;
+        self
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        self.line_start = c == Some('\n');
+        if self.line_start {
+            self.line += 1;
+        }
+        c
+    }
+
+    fn error(&self, message: &str) -> PgnError {
+        PgnError::Syntax {
+            line: self.line,
+            message: message.to_string(),
+        }
+    }
+
+    fn until(&mut self, end: char) -> Option<String> {
+        let mut rv = String::new();
+        loop {
+            match self.bump()? {
+                c if c == end => return Some(rv),
+                c => rv.push(c),
+            }
+        }
+    }
+
+    fn tag(&mut self) -> Result<Token, PgnError> {
+        let mut name = String::new();
+        while let Some(c) = self.chars.peek() {
+            if c.is_whitespace() || *c == '"' || *c == ']' {
+                break;
+            }
+            name.push(*c);
+            self.bump();
+        }
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.bump();
+        }
+        if name.is_empty() || self.bump() != Some('"') {
+            return Err(self.error("Tag without name and quoted value"));
+        }
+        let mut value = String::new();
+        loop {
+            match self.bump() {
+                Some('\\') => match self.bump() {
+                    Some(c) => value.push(c),
+                    None => return Err(self.error("Unterminated tag")),
+                },
+                Some('"') => break,
+                Some(c) => value.push(c),
+                None => return Err(self.error("Unterminated tag")),
+            }
+        }
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.bump();
+        }
+        if self.bump() != Some(']') {
+            return Err(self.error("Tag without closing bracket"));
+        }
+        Ok(Token::Tag(name, value))
+    }
+
+    fn symbol(&mut self, first: char) -> Option<Token> {
+        let mut s = first.to_string();
+        while let Some(c) = self.chars.peek() {
+            if c.is_whitespace() || "[]{}();$\"".contains(*c) {
+                break;
+            }
+            s.push(*c);
+            self.bump();
+        }
+        if let Ok(result) = s.parse::<GameResult>() {
+            return Some(Token::Result(result));
+        }
+        // Move numbers like `12.` or `12...` may be glued to the move
+        let rest = s.trim_start_matches(|c: char| c.is_ascii_digit());
+        let san = if rest.len() < s.len() && (rest.is_empty() || rest.starts_with('.')) {
+            rest.trim_start_matches('.')
+        } else {
+            &s
+        };
+        // Black moves may follow a bare `...`
+        if san.chars().all(|c| c == '.') {
+            None
+        } else {
+            Some(Token::San(san.to_string()))
+        }
+    }
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = Result<(Token, usize), PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.line_start && self.chars.peek() == Some(&'%') {
+                self.until('\n');
+                continue;
+            }
+            let c = self.bump()?;
+            let token = match c {
+                c if c.is_whitespace() => continue,
+                '[' => self.tag(),
+                '{' => match self.until('}') {
+                    // Line breaks inside a comment are only layout
+                    Some(comment) => Ok(Token::Comment(
+                        comment.split_whitespace().collect::<Vec<_>>().join(" "),
+                    )),
+                    None => Err(self.error("Unterminated comment")),
+                },
+                ';' => {
+                    let comment = self.until('\n').unwrap_or_default();
+                    Ok(Token::Comment(comment.trim().to_string()))
+                }
+                '(' => Ok(Token::Open),
+                ')' => Ok(Token::Close),
+                '$' => {
+                    let mut digits = String::new();
+                    while let Some(c) = self.chars.peek().filter(|c| c.is_ascii_digit()) {
+                        digits.push(*c);
+                        self.bump();
+                    }
+                    digits
+                        .parse::<u8>()
+                        .map(Token::Nag)
+                        .map_err(|_| self.error("Invalid NAG"))
+                }
+                c => match self.symbol(c) {
+                    Some(token) => Ok(token),
+                    None => continue,
+                },
+            };
+            return Some(token.map(|t| (t, self.line)));
+        }
+    }
+}
+
+type Tokens<'a> = Peekable<Lexer<'a>>;
+
+fn parse_game(tokens: &mut Tokens) -> Result<Option<PgnGame>, PgnError> {
+    let mut tags = Vec::new();
+    while let Some(Ok((Token::Tag(_, _), _))) = tokens.peek() {
+        if let Some(Ok((Token::Tag(name, value), _))) = tokens.next() {
+            tags.push((name, value));
+        }
+    }
+    if tags.is_empty() && tokens.peek().is_none() {
+        return Ok(None);
+    }
+    let fen = tags.iter().find(|(n, _)| n == "FEN").map(|(_, v)| v);
+    let start = match fen {
+        Some(fen) => from_fen(fen).map_err(PgnError::Fen)?,
+        None => Board::initial(),
+    };
+    let (moves, comments) = parse_line(tokens, start.clone(), 0, false)?;
+    let result = match tokens.peek() {
+        Some(Ok((Token::Result(r), _))) => {
+            let r = *r;
+            tokens.next();
+            r
+        }
+        _ => tags
+            .iter()
+            .find(|(n, _)| n == "Result")
+            .and_then(|(_, v)| v.parse().ok())
+            .unwrap_or(GameResult::Unknown),
+    };
+    Ok(Some(PgnGame {
+        tags,
+        start,
+        moves,
+        result,
+        comments,
+    }))
+}
+
+/// Parses moves up to the end of a game or variation. Returns the moves and
+/// comments which do not belong to any move.
+fn parse_line(
+    tokens: &mut Tokens,
+    mut b: Board,
+    ply: usize,
+    variation: bool,
+) -> Result<(Vec<PgnMove>, Vec<String>), PgnError> {
+    let mut moves: Vec<PgnMove> = Vec::new();
+    let mut pending = Vec::new();
+    let mut before = b.clone();
+    loop {
+        match tokens.peek() {
+            None | Some(Ok((Token::Tag(_, _), _))) | Some(Ok((Token::Result(_), _))) => break,
+            Some(Ok((Token::Close, _))) if variation => break,
+            _ => {}
+        }
+        let (token, line) = tokens.next().expect("Peeked token")?;
+        let syntax = |message: &str| PgnError::Syntax {
+            line,
+            message: message.to_string(),
+        };
+        match token {
+            Token::Comment(c) => match moves.last_mut() {
+                Some(m) => m.comments.push(c),
+                None => pending.push(c),
+            },
+            Token::Nag(n) => match moves.last_mut() {
+                Some(m) => m.nags.push(n),
+                None => return Err(syntax("NAG before any move")),
+            },
+            Token::Open => {
+                if moves.is_empty() {
+                    return Err(syntax("Variation before any move"));
+                }
+                let start = ply + moves.len() - 1;
+                let (line, _) = parse_line(tokens, before.clone(), start, true)?;
+                match tokens.next() {
+                    Some(Ok((Token::Close, _))) => {}
+                    Some(Err(e)) => return Err(e),
+                    _ => return Err(syntax("Unterminated variation")),
+                }
+                if let Some(m) = moves.last_mut() {
+                    m.variations.push(line);
+                }
+            }
+            Token::Close => return Err(syntax("Unmatched ')'")),
+            Token::San(s) => {
+                let (san, nag) = split_suffix(&s);
+                let mv = Move::from_san(san, &b).map_err(|error| PgnError::Move {
+                    ply: ply + moves.len() + 1,
+                    error,
+                })?;
+                before = b.clone();
+                b.make_move(mv);
+                let mut m = PgnMove::new(mv);
+                m.nags.extend(nag);
+                m.comments_before = mem::take(&mut pending);
+                moves.push(m);
+            }
+            Token::Tag(_, _) | Token::Result(_) => unreachable!(),
+        }
+    }
+    Ok((moves, pending))
+}
+
+/// Splits `!` and `?` suffixes off a move and returns them as a NAG
+fn split_suffix(s: &str) -> (&str, Option<u8>) {
+    let san = s.trim_end_matches(['!', '?']);
+    let nag = match &s[san.len()..] {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    };
+    (san, nag)
+}
+
+/// Callbacks of `PgnReader::visit` in the order of the text. `begin_game`
+/// and `end_game` enclose the other calls of each game.
+pub trait PgnVisitor {
+    fn begin_game(&mut self) {}
+    fn tag(&mut self, _name: &str, _value: &str) {}
+    /// Move in SAN without `!` and `?` suffixes, which are passed to `nag`.
+    /// `mv` is only set when the reader validates moves.
+    fn san(&mut self, _san: &str, _mv: Option<Move>) {}
+    fn nag(&mut self, _nag: u8) {}
+    fn comment(&mut self, _comment: &str) {}
+    fn begin_variation(&mut self) {}
+    fn end_variation(&mut self) {}
+    /// Result from the movetext, or from the `Result` tag when missing
+    fn end_game(&mut self, _result: GameResult) {}
+}
+
+/// Reads games one at a time from a buffered reader, so only the text of the
+/// current game is kept in memory. A game ends after its result, or without
+/// one at a line with an `Event` tag or the first tag after movetext. After a
+/// malformed game without a result the reader skips everything up to the
+/// next line starting with `[Event`.
+pub struct PgnReader<R> {
+    reader: R,
+    line: usize,
+    /// First line of the next game with its number
+    next: Option<(String, usize)>,
+    resync: bool,
+    done: bool,
+    validate: bool,
+}
+
+impl PgnReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(PgnReader::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> Self {
+        PgnReader {
+            reader,
+            line: 0,
+            next: None,
+            resync: false,
+            done: false,
+            validate: true,
+        }
+    }
+
+    /// Whether `visit` checks that moves are legal, on by default. Skipping
+    /// the check is faster, but the visitor gets no `Move`. Games returned
+    /// by the iterator are always checked.
+    pub fn validate(mut self, validate: bool) -> Self {
+        self.validate = validate;
+        self
+    }
+
+    /// Visits the next game, returns `None` at the end of the input
+    pub fn visit<V: PgnVisitor>(&mut self, visitor: &mut V) -> Option<Result<(), PgnError>> {
+        let (text, line, complete) = match self.read_game() {
+            Ok(Some(game)) => game,
+            Ok(None) => return None,
+            Err(e) => return Some(Err(e)),
+        };
+        let rv = visit_game(&text, line, visitor, self.validate);
+        self.resync = rv.is_err() && !complete;
+        Some(rv)
+    }
+
+    fn read_line(&mut self) -> Result<Option<String>, PgnError> {
+        let mut bytes = Vec::new();
+        match self.reader.read_until(b'\n', &mut bytes) {
+            Ok(0) => Ok(None),
+            Ok(_) => {
+                self.line += 1;
+                let text = match bytes.strip_prefix(b"\xef\xbb\xbf") {
+                    Some(rest) if self.line == 1 => rest,
+                    _ => &bytes[..],
+                };
+                // Old databases are often Latin-1 rather than UTF-8
+                Ok(Some(String::from_utf8_lossy(text).into_owned()))
+            }
+            Err(e) => {
+                self.done = true;
+                Err(PgnError::Io(Arc::new(e)))
+            }
+        }
+    }
+
+    /// Reads the text of the next game with the number of its first line. A
+    /// game ends after its result or before the tags of the next one; the
+    /// flag tells whether it ended at a result.
+    fn read_game(&mut self) -> Result<Option<(String, usize, bool)>, PgnError> {
+        let mut text = String::new();
+        let mut start = 0;
+        let mut movetext = false;
+        let mut scan = ResultScan::default();
+        let mut complete = false;
+        while !self.done {
+            let (line, number) = match self.next.take() {
+                Some(next) => next,
+                None => match self.read_line()? {
+                    Some(line) => (line, self.line),
+                    None => break,
+                },
+            };
+            // Wrapped comments and variations may start a line with `[` too
+            let trimmed = Some(line.trim_start()).filter(|_| !scan.comment && scan.depth == 0);
+            let starts = |p: &str| trimmed.is_some_and(|t| t.starts_with(p));
+            let event = starts("[Event");
+            if self.resync && !event {
+                continue;
+            }
+            self.resync = false;
+            let tag = starts("[");
+            if tag && (movetext || (event && !text.trim().is_empty())) {
+                self.next = Some((line, number));
+                break;
+            }
+            let escape = starts("%");
+            movetext |= !tag && !line.trim().is_empty() && !escape;
+            if text.is_empty() {
+                start = number;
+            }
+            let end = if tag || escape {
+                None
+            } else {
+                scan.result_end(&line)
+            };
+            if let Some(end) = end {
+                // Another game may follow on the same line
+                let rest = &line[end..];
+                if !rest.trim().is_empty() {
+                    self.next = Some((rest.to_string(), number));
+                }
+                text.push_str(&line[..end]);
+                text.push('\n');
+                complete = true;
+                break;
+            }
+            text.push_str(&line);
+        }
+        if text.trim().is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some((text, start, complete)))
+        }
+    }
+}
+
+/// Finds the end of a game's result in movetext read line by line, skipping
+/// comments and variations
+#[derive(Default)]
+struct ResultScan {
+    comment: bool,
+    depth: usize,
+}
+
+impl ResultScan {
+    /// Byte offset after the result token if `line` has one
+    fn result_end(&mut self, line: &str) -> Option<usize> {
+        let mut word = None;
+        for (idx, c) in line.char_indices() {
+            if self.comment {
+                self.comment = c != '}';
+                continue;
+            }
+            let delimiter = c.is_whitespace() || "{}();".contains(c);
+            if !delimiter {
+                word.get_or_insert(idx);
+                continue;
+            }
+            if let Some(begin) = word.take() {
+                if self.depth == 0 && is_result(&line[begin..idx]) {
+                    return Some(idx);
+                }
+            }
+            match c {
+                '{' => self.comment = true,
+                '(' => self.depth += 1,
+                ')' => self.depth = self.depth.saturating_sub(1),
+                ';' => return None,
+                _ => {}
+            }
+        }
+        match word {
+            Some(begin) if self.depth == 0 && is_result(&line[begin..]) => Some(line.len()),
+            _ => None,
+        }
+    }
+}
+
+fn is_result(word: &str) -> bool {
+    matches!(word, "1-0" | "0-1" | "1/2-1/2" | "*")
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (text, line, complete) = match self.read_game() {
+                Ok(Some(game)) => game,
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e)),
+            };
+            let mut tokens = Lexer::new(&text).starting_at(line).peekable();
+            match parse_game(&mut tokens) {
+                Ok(Some(game)) => return Some(Ok(game)),
+                // Only comments or escaped lines
+                Ok(None) => {}
+                Err(e) => {
+                    // Without a result the rest of the game may be left over
+                    self.resync = !complete;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// Line of moves while visiting, the boards are only kept up to date when
+/// moves are validated
+struct VisitLine {
+    before: Board,
+    board: Board,
+    ply: usize,
+    moves: usize,
+}
+
+impl VisitLine {
+    fn new(board: Board, ply: usize) -> Self {
+        VisitLine {
+            before: board.clone(),
+            board,
+            ply,
+            moves: 0,
+        }
+    }
+}
+
+fn visit_game<V: PgnVisitor>(
+    text: &str,
+    line: usize,
+    visitor: &mut V,
+    validate: bool,
+) -> Result<(), PgnError> {
+    let mut lines: Vec<VisitLine> = Vec::new();
+    let mut movetext = false;
+    let mut result = GameResult::Unknown;
+    let mut last = line;
+    for token in Lexer::new(text).starting_at(line) {
+        let (token, line) = token?;
+        last = line;
+        let syntax = |message: &str| PgnError::Syntax {
+            line,
+            message: message.to_string(),
+        };
+        if matches!(token, Token::Tag(_, _)) && movetext {
+            visitor.end_game(result);
+            lines.clear();
+        }
+        if lines.is_empty() {
+            visitor.begin_game();
+            lines.push(VisitLine::new(Board::initial(), 0));
+            movetext = false;
+            result = GameResult::Unknown;
+        }
+        movetext |= !matches!(token, Token::Tag(_, _));
+        let depth = lines.len();
+        let top = lines.last_mut().expect("Line of the game");
+        match token {
+            Token::Tag(name, value) => {
+                if name == "FEN" && validate {
+                    *top = VisitLine::new(from_fen(&value).map_err(PgnError::Fen)?, 0);
+                }
+                if name == "Result" {
+                    result = value.parse().unwrap_or(GameResult::Unknown);
+                }
+                visitor.tag(&name, &value);
+            }
+            Token::Comment(c) => visitor.comment(&c),
+            Token::Nag(n) if top.moves > 0 => visitor.nag(n),
+            Token::Nag(_) => return Err(syntax("NAG before any move")),
+            Token::Open if top.moves > 0 => {
+                let variation = VisitLine::new(top.before.clone(), top.ply - 1);
+                lines.push(variation);
+                visitor.begin_variation();
+            }
+            Token::Open => return Err(syntax("Variation before any move")),
+            Token::Close if depth > 1 => {
+                lines.pop();
+                visitor.end_variation();
+            }
+            Token::Close => return Err(syntax("Unmatched ')'")),
+            Token::San(s) => {
+                let (san, nag) = split_suffix(&s);
+                let mv = if validate {
+                    let mv = Move::from_san(san, &top.board).map_err(|error| PgnError::Move {
+                        ply: top.ply + 1,
+                        error,
+                    })?;
+                    top.before = top.board.clone();
+                    top.board.make_move(mv);
+                    Some(mv)
+                } else {
+                    None
+                };
+                top.ply += 1;
+                top.moves += 1;
+                visitor.san(san, mv);
+                if let Some(nag) = nag {
+                    visitor.nag(nag);
+                }
+            }
+            Token::Result(r) if depth == 1 => {
+                visitor.end_game(r);
+                lines.clear();
+            }
+            Token::Result(_) => return Err(syntax("Unterminated variation")),
+        }
+    }
+    match lines.len() {
+        0 => Ok(()),
+        1 => {
+            visitor.end_game(result);
+            Ok(())
+        }
+        _ => Err(PgnError::Syntax {
+            line: last,
+            message: "Unterminated variation".to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::to_fen;
+    use crate::move_generator::generate_legal;
+
+    const GAME: &str = r#"[Event "F/S Return Match"]
+[Site "Belgrade, Serbia JUG"]
+[Date "1992.11.04"]
+[Round "29"]
+[White "Fischer, Robert J."]
+[Black "Spassky, Boris V."]
+[Result "1/2-1/2"]
+[Annotator "A \"quoted\" name"]
+
+{Opening comment} 1. e4 e5 2. Nf3 Nc6 3. Bb5 {This opening is called the Ruy Lopez.}
+3... a6 $1 4. Ba4 Nf6 5. O-O Be7 (5... b5 6. Bb3 (6. Bxb5?! axb5) Bc5) 6. Re1 b5!
+; line comment
+7. Bb3 d6 8. c3 O-O 9. h3 Nb8 10. d4 Nbd7 11. c4 c6 12. cxb5 axb5 13. Nc3 Bb7
+14. Bg5 b4 15. Nb1 h6 16. Bh4 c5 17. dxe5 Nxe4 18. Bxe7 Qxe7 19. exd6 Qf6
+20. Nbd2 Nxd6 21. Nc4 Nxc4 22. Bxc4 Nb6 23. Ne5 Rae8 24. Bxf7+ Rxf7 25. Nxf7
+Rxe1+ 26. Qxe1 Kxf7 27. Qe3 Qg5 28. Qxg5 hxg5 29. b3 Ke6 30. a3 Kd6 31. axb4
+cxb4 32. Ra5 Nd5 33. f3 Bc8 34. Kf2 Bf5 35. Ra7 g6 36. Ra6+ Kc5 37. Ke1 Nf4
+38. g3 Nxh3 39. Kd2 Kb5 40. Rd6 Kc5 41. Ra6 Nf2 42. g4 Bd3 43. Re6 1/2-1/2
+"#;
+
+    fn uci(game: &[PgnMove], start: &Board) -> Vec<String> {
+        let mut b = start.clone();
+        game.iter()
+            .map(|m| {
+                let rv = m.mv.to_uci();
+                b.make_move(m.mv);
+                rv
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_game() {
+        let game = GAME.parse::<PgnGame>().unwrap();
+        assert_eq!(game.tags.len(), 8);
+        assert_eq!(game.tag("White"), Some("Fischer, Robert J."));
+        assert_eq!(game.tag("Annotator"), Some("A \"quoted\" name"));
+        assert_eq!(game.tag("ECO"), None);
+        assert_eq!(game.result, GameResult::Draw);
+        assert_eq!(game.moves.len(), 85);
+        assert_eq!(game.start, Board::initial());
+        assert_eq!(
+            to_fen(&game.end()),
+            "8/8/4R1p1/2k3p1/1p4P1/1P1b1P2/3K1n2/8 b - - 2 43"
+        );
+
+        let m = &game.moves;
+        assert_eq!(m[0].comments_before, vec!["Opening comment"]);
+        assert_eq!(m[4].comments, vec!["This opening is called the Ruy Lopez."]);
+        assert_eq!(m[5].nags, vec![1]);
+        assert_eq!(m[11].nags, vec![1]);
+        assert_eq!(m[11].comments, vec!["line comment"]);
+        assert!(m[8].mv.is_castle());
+        // 5... b5 6. Bb3 (6. Bxb5?! axb5) Bc5 replaces 5... Be7
+        assert_eq!(m[9].variations.len(), 1);
+        let variation = &m[9].variations[0];
+        let mut b = game.start.clone();
+        for m in &m[..9] {
+            b.make_move(m.mv);
+        }
+        assert_eq!(uci(variation, &b), vec!["b7b5", "a4b3", "f8c5"]);
+        b.make_move(variation[0].mv);
+        assert_eq!(uci(&variation[1].variations[0], &b), vec!["a4b5", "a6b5"]);
+        assert_eq!(variation[1].variations[0][0].nags, vec![6]);
+    }
+
+    #[test]
+    fn several_games() {
+        let text = format!(
+            "{}\n[Event \"?\"]\n[SetUp \"1\"]\n[FEN \"{}\"]\n\n1... Kb8 2. Qg8# 1-0\n\n1. d4 *",
+            GAME, "k7/8/1K6/8/8/8/8/6Q1 b - - 0 1"
+        );
+        let games = from_pgn(&text).unwrap();
+        assert_eq!(games.len(), 3);
+        assert_eq!(
+            games[1].start,
+            from_fen("k7/8/1K6/8/8/8/8/6Q1 b - - 0 1").unwrap()
+        );
+        assert_eq!(uci(&games[1].moves, &games[1].start), vec!["a8b8", "g1g8"]);
+        assert_eq!(games[1].result, GameResult::WhiteWins);
+        assert!(games[2].tags.is_empty());
+        assert_eq!(games[2].result, GameResult::Unknown);
+        assert_eq!(games[2].mainline().len(), 1);
+        assert!(from_pgn("").unwrap().is_empty());
+        assert_eq!("  ".parse::<PgnGame>(), Err(PgnError::Empty));
+    }
+
+    #[test]
+    fn tolerant() {
+        let game = "1.e4 e5 2.Nf3 ... Nc6 3.Bb5!? {no result}"
+            .parse::<PgnGame>()
+            .unwrap();
+        assert_eq!(game.moves.len(), 5);
+        assert_eq!(game.moves[4].nags, vec![5]);
+        assert_eq!(game.result, GameResult::Unknown);
+        let game = "[Result \"0-1\"]\n%escaped line\n1. f3 e5 2. g4 Qh4#"
+            .parse::<PgnGame>()
+            .unwrap();
+        assert_eq!(game.result, GameResult::BlackWins);
+        assert_eq!(game.moves.len(), 4);
+        let game = "{Only a comment} *".parse::<PgnGame>().unwrap();
+        assert!(game.moves.is_empty());
+        assert_eq!(game.comments, vec!["Only a comment"]);
+        let text = "\u{feff}[Event \"A\"]\r\n\r\n1. e4 e5 1-0";
+        let games = from_pgn(text).unwrap();
+        assert_eq!(games[0].tag("Event"), Some("A"));
+        assert_eq!(games[0].mainline().len(), 2);
+        let games: Vec<_> = PgnReader::new(text.as_bytes()).collect();
+        assert_eq!(games, vec![Ok(from_pgn(text).unwrap().remove(0))]);
+    }
+
+    #[test]
+    fn errors() {
+        let err = |s: &str| s.parse::<PgnGame>().unwrap_err();
+        assert_eq!(
+            err("1. e4 e5 2. Ke3"),
+            PgnError::Move {
+                ply: 3,
+                error: SanError::Illegal("Ke3".to_string())
+            }
+        );
+        assert_eq!(
+            err("1. e4 (1. d4 d5 2. Qd3 Qd3) e5"),
+            PgnError::Move {
+                ply: 4,
+                error: SanError::Illegal("Qd3".to_string())
+            }
+        );
+        assert!(matches!(err("[FEN \"8/8 w\"]\n*"), PgnError::Fen(_)));
+        assert!(matches!(
+            err("1. e4 {open\n"),
+            PgnError::Syntax { line: 2, .. }
+        ));
+        assert!(matches!(
+            err("\n1. e4 e5)"),
+            PgnError::Syntax { line: 2, .. }
+        ));
+        assert!(matches!(err("1. e4 (1. d4"), PgnError::Syntax { .. }));
+        assert!(matches!(err("(1. e4)"), PgnError::Syntax { .. }));
+        assert!(matches!(err("$1 e4"), PgnError::Syntax { .. }));
+        assert!(matches!(err("[Event ?]"), PgnError::Syntax { .. }));
+        assert_eq!(
+            PgnError::Move {
+                ply: 3,
+                error: SanError::Illegal("Ke3".to_string())
+            }
+            .to_string(),
+            "Ply 3: Illegal move 'Ke3'"
+        );
+    }
+
+    #[test]
+    fn moves_are_legal() {
+        let game = GAME.parse::<PgnGame>().unwrap();
+        let mut b = game.start.clone();
+        for m in game.mainline() {
+            assert!(generate_legal(&b).contains(&m));
+            b.make_move(m);
+        }
+    }
+
+    #[test]
+    fn write() {
+        let game = "[White \"Me\"]\n[ECO \"C20\"]\n[Black \"You\"]\n\
+            {Start} 1. e4 e5 {[%clk 0:05:00] solid} 2. Nf3 $1 (2. f4 exf4) 2... Nc6 1-0"
+            .parse::<PgnGame>()
+            .unwrap();
+        let expected = "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n\
+            [White \"Me\"]\n[Black \"You\"]\n[Result \"1-0\"]\n[ECO \"C20\"]\n\n\
+            {Start} 1. e4 e5 {[%clk 0:05:00] solid} 2. Nf3 $1 (2. f4 exf4) 2... Nc6 1-0\n";
+        assert_eq!(game.to_pgn(&PgnOptions::default()), expected);
+        assert_eq!(game.to_string(), expected);
+
+        let options = PgnOptions {
+            comments: false,
+            nags: false,
+            variations: false,
+            ..PgnOptions::default()
+        };
+        assert!(game
+            .to_pgn(&options)
+            .ends_with("\n1. e4 e5 {[%clk 0:05:00]} 2. Nf3 Nc6 1-0\n"));
+        let options = PgnOptions {
+            clocks: false,
+            ..PgnOptions::default()
+        };
+        assert!(game
+            .to_pgn(&options)
+            .ends_with("\n{Start} 1. e4 e5 {solid} 2. Nf3 $1 (2. f4 exf4) 2... Nc6 1-0\n"));
+    }
+
+    #[test]
+    fn write_fen() {
+        let fen = "k7/8/1K6/8/8/8/8/6Q1 b - - 0 1";
+        let text = format!("[FEN \"{}\"]\n[SetUp \"1\"]\n1... Kb8 2. Qg8# 1-0", fen);
+        let game = text.parse::<PgnGame>().unwrap();
+        let pgn = game.to_pgn(&PgnOptions::default());
+        assert!(pgn.contains(&format!(
+            "[Result \"1-0\"]\n[FEN \"{}\"]\n[SetUp \"1\"]\n\n1... Kb8 2. Qg8# 1-0\n",
+            fen
+        )));
+        let options = PgnOptions {
+            fen: false,
+            ..PgnOptions::default()
+        };
+        assert!(!game.to_pgn(&options).contains("FEN"));
+        let quoted = r#"[Event "A \"B\" \\ C"] *"#.parse::<PgnGame>().unwrap();
+        assert!(quoted.to_string().starts_with(r#"[Event "A \"B\" \\ C"]"#));
+    }
+
+    #[test]
+    fn write_round_trip() {
+        let game = GAME.parse::<PgnGame>().unwrap();
+        let pgn = game.to_string();
+        assert!(pgn.lines().all(|l| l.len() <= 80));
+        assert!(pgn
+            .replace('\n', " ")
+            .contains("5. O-O Be7 (5... b5 6. Bb3 (6. Bxb5 $6 axb5) 6... Bc5) 6. Re1 b5 $1"));
+        let again = pgn.parse::<PgnGame>().unwrap();
+        assert_eq!(again.moves, game.moves);
+        assert_eq!(again.to_string(), pgn);
+        let games = from_pgn(&to_pgn(&[game.clone(), again], &PgnOptions::default())).unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[1].moves, game.moves);
+    }
+
+    #[test]
+    fn reader() {
+        let text = format!(
+            "{}\n[Event \"Broken\"]\n\n1. e4 e5 2. Ke3 *\n[Site \"?\"]\n\n1. d4 *\n\n\
+            [Event \"Next\"]\n[Result \"0-1\"]\n\n1. f3 e5 2. g4 Qh4# 0-1\n\n1. c4 *\n",
+            GAME
+        );
+        let games: Vec<_> = PgnReader::new(text.as_bytes()).collect();
+        assert_eq!(games.len(), 5);
+        assert_eq!(games[0], Ok(GAME.parse::<PgnGame>().unwrap()));
+        assert!(matches!(games[1], Err(PgnError::Move { ply: 3, .. })));
+        // The broken game ended at its result, so the next one is read
+        assert_eq!(games[2].as_ref().unwrap().tag("Site"), Some("?"));
+        let game = games[3].as_ref().unwrap();
+        assert_eq!(game.tag("Event"), Some("Next"));
+        assert_eq!(game.result, GameResult::BlackWins);
+        assert_eq!(games[4].as_ref().unwrap().mainline().len(), 1);
+
+        // Tags inside an open comment are part of the comment
+        let mut reader =
+            PgnReader::new("[Event \"A\"]\n\n1. e4 {\n\n[Event \"B\"]\n1. d4 *".as_bytes());
+        assert_eq!(
+            reader.next(),
+            Some(Err(PgnError::Syntax {
+                line: 6,
+                message: "Unterminated comment".to_string()
+            }))
+        );
+        assert_eq!(reader.next(), None);
+        let mut reader =
+            PgnReader::new("[Event \"A\"]\n\n1. e4 e5\n\n[Event \"B\"]\n1. d4 *".as_bytes());
+        assert_eq!(reader.next().unwrap().unwrap().mainline().len(), 2);
+        assert_eq!(reader.next().unwrap().unwrap().tag("Event"), Some("B"));
+        assert_eq!(reader.next(), None);
+        assert_eq!(PgnReader::new(&b"\n \n"[..]).next(), None);
+    }
+
+    #[test]
+    fn reader_without_tags() {
+        let mut text = String::new();
+        for _ in 0..1000 {
+            text.push_str("1. e4 e5 2. Nf3 1-0\n");
+        }
+        text.push_str("1. d4 { 1-0 is not the end } d5 (1... Nf6 2. c4) 2. c4 *\n");
+        text.push_str("1. c4 1/2-1/2 1. f4 0-1\n");
+        let mut reader = PgnReader::new(text.as_bytes());
+        let game = reader.next().unwrap().unwrap();
+        assert_eq!(game.mainline().len(), 3);
+        assert_eq!(game.result, GameResult::WhiteWins);
+        // Only the line of the first game has been read
+        assert_eq!(reader.line, 1);
+        assert_eq!(reader.by_ref().take(999).filter(|g| g.is_ok()).count(), 999);
+        assert_eq!(reader.line, 1000);
+        let game = reader.next().unwrap().unwrap();
+        assert_eq!(game.mainline().len(), 3);
+        assert_eq!(game.moves[0].comments, vec!["1-0 is not the end"]);
+        assert_eq!(game.moves[1].variations.len(), 1);
+        let results: Vec<_> = reader.map(|g| g.unwrap().result).collect();
+        assert_eq!(results, vec![GameResult::Draw, GameResult::BlackWins]);
+
+        let mut reader = PgnReader::new(text.as_bytes());
+        let mut counter = Counter::default();
+        assert_eq!(reader.visit(&mut counter), Some(Ok(())));
+        assert_eq!((counter.games, reader.line), (1, 1));
+        while let Some(rv) = reader.visit(&mut counter) {
+            rv.unwrap();
+        }
+        assert_eq!(counter.games, 1003);
+    }
+
+    #[test]
+    fn reader_wrapped_comment() {
+        let mut wrapped = false;
+        for n in 30..40 {
+            let text = format!("1. e4 {{{}[%clk 0:05:00] y}} e5 *", "x ".repeat(n));
+            let pgn = text.parse::<PgnGame>().unwrap().to_string();
+            wrapped |= pgn.lines().any(|l| l.starts_with("[%clk"));
+            let games: Vec<_> = PgnReader::new(pgn.as_bytes()).collect();
+            assert_eq!(games.len(), 1, "{}", pgn);
+            let game = games[0].as_ref().unwrap();
+            assert_eq!(game.mainline().len(), 2);
+            assert!(game.moves[0].comments[0].ends_with("[%clk 0:05:00] y"));
+        }
+        assert!(wrapped);
+    }
+
+    struct Failing;
+
+    impl io::Read for Failing {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "disk gone"))
+        }
+    }
+
+    #[test]
+    fn read_error() {
+        let mut reader = PgnReader::new(BufReader::new(Failing));
+        let e = reader.next().unwrap().unwrap_err();
+        assert_eq!(e.to_string(), "disk gone");
+        let source = e.source().unwrap().downcast_ref::<io::Error>().unwrap();
+        assert_eq!(source.kind(), io::ErrorKind::BrokenPipe);
+        assert_eq!(e.clone(), e);
+        assert_ne!(e, PgnError::Empty);
+        assert_eq!(reader.next(), None);
+    }
+
+    #[derive(Default)]
+    struct Counter {
+        games: usize,
+        tags: usize,
+        moves: Vec<String>,
+        legal: usize,
+        nags: Vec<u8>,
+        comments: usize,
+        depth: usize,
+        max_depth: usize,
+        results: Vec<GameResult>,
+    }
+
+    impl PgnVisitor for Counter {
+        fn begin_game(&mut self) {
+            self.games += 1;
+        }
+        fn tag(&mut self, _name: &str, _value: &str) {
+            self.tags += 1;
+        }
+        fn san(&mut self, san: &str, mv: Option<Move>) {
+            self.moves.push(san.to_string());
+            self.legal += mv.is_some() as usize;
+        }
+        fn nag(&mut self, nag: u8) {
+            self.nags.push(nag);
+        }
+        fn comment(&mut self, _comment: &str) {
+            self.comments += 1;
+        }
+        fn begin_variation(&mut self) {
+            self.depth += 1;
+            self.max_depth = self.max_depth.max(self.depth);
+        }
+        fn end_variation(&mut self) {
+            self.depth -= 1;
+        }
+        fn end_game(&mut self, result: GameResult) {
+            self.results.push(result);
+        }
+    }
+
+    #[test]
+    fn visitor() {
+        let mut reader = PgnReader::new(GAME.as_bytes());
+        let mut counter = Counter::default();
+        assert_eq!(reader.visit(&mut counter), Some(Ok(())));
+        assert_eq!(reader.visit(&mut counter), None);
+        assert_eq!(counter.games, 1);
+        assert_eq!(counter.tags, 8);
+        assert_eq!(counter.moves.len(), 85 + 5);
+        assert_eq!(counter.legal, 90);
+        assert_eq!(counter.moves[5], "a6");
+        assert_eq!(counter.nags, vec![1, 6, 1]);
+        assert_eq!(counter.comments, 3);
+        assert_eq!((counter.depth, counter.max_depth), (0, 2));
+        assert_eq!(counter.results, vec![GameResult::Draw]);
+
+        // Without validation illegal moves pass
+        let text = "[Result \"1-0\"]\n1. e4 e5 2. Ke3 (2. Kz9) \n\n1. d4";
+        let mut counter = Counter::default();
+        let mut reader = PgnReader::new(text.as_bytes()).validate(false);
+        assert_eq!(reader.visit(&mut counter), Some(Ok(())));
+        assert_eq!(counter.moves, vec!["e4", "e5", "Ke3", "Kz9", "d4"]);
+        assert_eq!(counter.legal, 0);
+        assert_eq!(counter.results, vec![GameResult::WhiteWins]);
+        let mut reader = PgnReader::new(text.as_bytes());
+        assert!(matches!(
+            reader.visit(&mut Counter::default()),
+            Some(Err(PgnError::Move { ply: 3, .. }))
+        ));
+        let mut reader = PgnReader::new("1. e4 *\n1. d4 (1. c4 *".as_bytes()).validate(false);
+        let mut counter = Counter::default();
+        assert_eq!(reader.visit(&mut counter), Some(Ok(())));
+        assert!(matches!(
+            reader.visit(&mut counter),
+            Some(Err(PgnError::Syntax { line: 2, .. }))
+        ));
+        assert_eq!(counter.games, 2);
+    }
+}
@@ -157,6 +157,13 @@ impl Side {
             Side::Black => 'b',
         }
     }
+
+    pub fn opposite(&self) -> Side {
+        match *self {
+            Side::White => Side::Black,
+            Side::Black => Side::White,
+        }
+    }
 }
 
 impl fmt::Display for Side {
@@ -0,0 +1,116 @@
+use crate::board::Board;
+use crate::game::Game;
+use crate::zobrist::legal_en_passant;
+
+impl Board {
+    /// Checks whether two boards are the same position for the repetition
+    /// rules: same pieces, side to move, castling rights and en-passant field
+    /// when a capture there is legal. Unlike `==` the clocks are ignored.
+    pub fn same_position(&self, other: &Board) -> bool {
+        // The stored keys rule out most positions before the fields are compared
+        self.hash() == other.hash()
+            && self.pieces == other.pieces
+            && self.active == other.active
+            && self.can_castle == other.can_castle
+            && (self.en_passant == other.en_passant
+                || legal_en_passant(self) == legal_en_passant(other))
+    }
+}
+
+impl Game {
+    /// Number of times the position after the last move has occurred,
+    /// counting itself. Positions before the last capture or pawn move are
+    /// not compared, as they cannot come back.
+    pub fn repetitions(&self) -> usize {
+        let last = self.position(self.len()).expect("Position after last move");
+        let reversible = (last.halfmove_clock as usize).min(self.len());
+        (0..=reversible)
+            .step_by(2)
+            .filter_map(|back| self.position(self.len() - back))
+            .filter(|b| b.same_position(last))
+            .count()
+    }
+
+    /// The position occurred three times, so either player may claim a draw
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.repetitions() >= 3
+    }
+
+    /// The position occurred five times, which ends the game in a draw
+    pub fn is_fivefold_repetition(&self) -> bool {
+        self.repetitions() >= 5
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::from_fen;
+    use crate::mv::Move;
+    use crate::outcome::{Outcome, Termination};
+
+    fn play(game: &mut Game, moves: &str) {
+        for m in moves.split_whitespace() {
+            let m = Move::from_san(m, game.board()).unwrap();
+            game.push(m).unwrap();
+        }
+    }
+
+    #[test]
+    fn same_position() {
+        let a = from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        let b = from_fen("4k3/8/8/8/8/8/8/4K2R w K - 12 40").unwrap();
+        assert_ne!(a, b);
+        assert!(a.same_position(&b));
+        let c = from_fen("4k3/8/8/8/8/8/8/4K2R w - - 0 1").unwrap();
+        assert!(!a.same_position(&c));
+        // No pawn can take on e3
+        let a = from_fen("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1").unwrap();
+        let b = from_fen("4k3/8/8/8/4P3/8/8/4K3 b - - 0 1").unwrap();
+        assert!(a.same_position(&b));
+    }
+
+    #[test]
+    fn repetitions() {
+        let mut game = Game::new(Board::initial());
+        play(&mut game, "Nf3 Nf6 Ng1 Ng8");
+        assert_eq!(game.repetitions(), 2);
+        play(&mut game, "Nf3 Nf6 Ng1");
+        assert_eq!(game.repetitions(), 2);
+        play(&mut game, "Ng8");
+        assert!(game.is_threefold_repetition());
+        assert!(!game.is_fivefold_repetition());
+        assert_eq!(game.outcome(), None);
+        play(&mut game, "Nf3 Nf6 Ng1 Ng8 Nf3 Nf6 Ng1 Ng8");
+        assert_eq!(game.repetitions(), 5);
+        assert_eq!(
+            game.outcome(),
+            Some(Outcome::draw(Termination::FivefoldRepetition))
+        );
+
+        // The first position with the kings on e1 and e8 still had castling
+        // rights
+        let mut game = Game::new(Board::initial());
+        play(&mut game, "e4 e5 Ke2 Ke7 Ke1 Ke8");
+        assert_eq!(game.repetitions(), 1);
+        assert!(!game.claim_repetition());
+        play(&mut game, "Ke2 Ke7 Ke1 Ke8 Ke2 Ke7 Ke1 Ke8");
+        assert_eq!(game.repetitions(), 3);
+        assert!(game.claim_repetition());
+        assert_eq!(
+            game.outcome(),
+            Some(Outcome::draw(Termination::ThreefoldRepetition))
+        );
+    }
+
+    #[test]
+    fn en_passant() {
+        // After 1... c5 white could take en passant, later it cannot
+        let b = from_fen("4k3/2p5/8/3P4/8/8/8/4K3 b - - 0 1").unwrap();
+        let mut game = Game::new(b);
+        play(&mut game, "c5 Kd2 Kd8 Ke1 Ke8");
+        assert_eq!(game.repetitions(), 1);
+        play(&mut game, "Kd2 Kd8 Ke1 Ke8");
+        assert_eq!(game.repetitions(), 2);
+    }
+}
@@ -0,0 +1,315 @@
+use crate::board::Board;
+use crate::field::Field;
+use crate::move_generator::{generate_legal, in_check};
+use crate::mv::Move;
+use crate::piece::{ColoredPiece, Piece};
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SanError {
+    /// Text which is not a move in algebraic notation
+    Invalid(String),
+    /// Move which is not legal in the position
+    Illegal(String),
+    /// Move which matches several legal moves
+    Ambiguous(String),
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SanError::Invalid(s) => write!(f, "Invalid move notation '{}'", s),
+            SanError::Illegal(s) => write!(f, "Illegal move '{}'", s),
+            SanError::Ambiguous(s) => write!(f, "Ambiguous move '{}'", s),
+        }
+    }
+}
+
+impl Error for SanError {}
+
+impl Move {
+    /// Formats the move in Standard Algebraic Notation, e.g. `Nbd7`, `exd5`,
+    /// `O-O+` or `e8=Q#`. The move should be legal in the position.
+    pub fn to_san(&self, b: &Board) -> String {
+        let mut rv = match self {
+            Move::KingCastle(_) => "O-O".to_string(),
+            Move::QueenCastle(_) => "O-O-O".to_string(),
+            _ => piece_move_san(self, b),
+        };
+        let mut after = b.clone();
+        after.make_move(*self);
+        if in_check(&after, after.active) {
+            rv.push(if generate_legal(&after).is_empty() {
+                '#'
+            } else {
+                '+'
+            });
+        }
+        rv
+    }
+
+    /// Parses a move in Standard Algebraic Notation. Castling written with
+    /// zeros, promotions without `=`, long algebraic notation like `Ng1-f3`,
+    /// check and mate suffixes and annotations like `!?` are accepted.
+    pub fn from_san(s: &str, b: &Board) -> Result<Move, SanError> {
+        let invalid = || SanError::Invalid(s.to_string());
+        let san = s.trim().trim_end_matches(|c| "+#!?".contains(c));
+        let san = san.strip_suffix("e.p.").unwrap_or(san).trim_end();
+        let legal = generate_legal(b);
+        let castle = match san {
+            "O-O" | "0-0" => Some(true),
+            "O-O-O" | "0-0-0" => Some(false),
+            _ => None,
+        };
+        if let Some(king_side) = castle {
+            return legal
+                .into_iter()
+                .find(|m| match m {
+                    Move::KingCastle(_) => king_side,
+                    Move::QueenCastle(_) => !king_side,
+                    _ => false,
+                })
+                .ok_or_else(|| SanError::Illegal(s.to_string()));
+        }
+
+        let mut chars: Vec<char> = san.chars().filter(|c| !"x:-=()".contains(*c)).collect();
+        let promotion = match chars.as_slice() {
+            [.., rank, p] if rank.is_ascii_digit() && p.is_ascii_alphabetic() => {
+                let p = p.to_ascii_uppercase().to_string().parse::<Piece>();
+                chars.pop();
+                Some(p.map_err(|_| invalid())?)
+            }
+            _ => None,
+        };
+        let piece = match chars.first() {
+            Some(c) if c.is_ascii_uppercase() => {
+                let p = c.to_string().parse::<Piece>().map_err(|_| invalid())?;
+                chars.remove(0);
+                p
+            }
+            _ => Piece::Pawn,
+        };
+        if chars.len() < 2 || chars.len() > 4 {
+            return Err(invalid());
+        }
+        let (hint, to) = chars.split_at(chars.len() - 2);
+        let to = to
+            .iter()
+            .collect::<String>()
+            .parse::<Field>()
+            .map_err(|_| invalid())?;
+        let mut column = None;
+        let mut row = None;
+        for c in hint {
+            match c {
+                'a'..='h' if column.is_none() => column = Some(*c as usize - 'a' as usize + 1),
+                '1'..='8' if row.is_none() => row = Some(*c as usize - '1' as usize + 1),
+                _ => return Err(invalid()),
+            }
+        }
+
+        let mut candidates = legal.into_iter().filter(|m| match m.move_data() {
+            Some(d) => {
+                b.pieces[d.from().0] == ColoredPiece::P(piece, b.active)
+                    && d.to() == to
+                    && d.promotion() == promotion
+                    && (column.is_none() || column == Some(d.from().column()))
+                    && (row.is_none() || row == Some(d.from().row()))
+            }
+            None => false,
+        });
+        match (candidates.next(), candidates.next()) {
+            (Some(m), None) => Ok(m),
+            (None, _) => Err(SanError::Illegal(s.to_string())),
+            (Some(_), Some(_)) => Err(SanError::Ambiguous(s.to_string())),
+        }
+    }
+}
+
+fn piece_move_san(m: &Move, b: &Board) -> String {
+    let d = m.move_data().expect("Non-castling move has data");
+    let piece = match b.pieces[d.from().0] {
+        ColoredPiece::P(p, _) => p,
+        ColoredPiece::Empty => Piece::Pawn,
+    };
+    let is_capture = m.is_capture();
+    let mut rv = String::new();
+    if piece == Piece::Pawn {
+        if is_capture {
+            rv.push(file(d.from()));
+        }
+    } else {
+        rv.push_str(&piece.to_string());
+        // Other pieces of the same kind which can go to the same field
+        let rivals: Vec<Field> = generate_legal(b)
+            .iter()
+            .filter_map(|m| m.move_data())
+            .filter(|o| {
+                o.to() == d.to()
+                    && o.from() != d.from()
+                    && b.pieces[o.from().0] == b.pieces[d.from().0]
+            })
+            .map(|o| o.from())
+            .collect();
+        if !rivals.is_empty() {
+            let from = d.from().to_string();
+            if rivals.iter().all(|f| f.column() != d.from().column()) {
+                rv.push_str(&from[..1]);
+            } else if rivals.iter().all(|f| f.row() != d.from().row()) {
+                rv.push_str(&from[1..]);
+            } else {
+                rv.push_str(&from);
+            }
+        }
+    }
+    if is_capture {
+        rv.push('x');
+    }
+    rv.push_str(&d.to().to_string());
+    if let Some(p) = d.promotion() {
+        rv.push('=');
+        rv.push_str(&p.to_string());
+    }
+    rv
+}
+
+fn file(f: Field) -> char {
+    (b'a' + (f.column() - 1) as u8) as char
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::from_fen;
+    use crate::field::named::*;
+    use crate::mv::{en_passant, king_castle, queen_castle};
+
+    fn check(fen: &str, m: &str, san: &str) {
+        let b = from_fen(fen).unwrap();
+        // Move kinds and castling sides follow from the position
+        let m = generate_legal(&b)
+            .into_iter()
+            .find(|g| g.to_string() == m)
+            .unwrap_or_else(|| m.parse::<Move>().unwrap());
+        assert_eq!(m.to_san(&b), san);
+        assert_eq!(Move::from_san(san, &b), Ok(m));
+    }
+
+    #[test]
+    fn pieces_and_pawns() {
+        let initial = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        check(initial, "g1f3", "Nf3");
+        check(initial, "e2e4", "e4");
+        check(initial, "b2b3", "b3");
+        let fen = "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2";
+        check(fen, "e4xd5", "exd5");
+        check(fen, "f1b5", "Bb5+");
+        let fen = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3";
+        check(fen, "e5xf6", "exf6");
+    }
+
+    #[test]
+    fn disambiguation() {
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 0 1";
+        check(fen, "g8e7", "Nge7");
+        check(fen, "c6e7", "Nce7");
+        let fen = "4k3/8/8/8/R7/8/8/R3K3 w - - 0 1";
+        check(fen, "a1a3", "R1a3");
+        check(fen, "a4a3", "R4a3");
+        check(fen, "a1b1", "Rb1");
+        let fen = "1k6/8/8/8/4Q2Q/8/8/K6Q w - - 0 1";
+        check(fen, "h4e1", "Qh4e1");
+        check(fen, "e4e1", "Qee1");
+        check(fen, "h1e1", "Q1e1");
+        let fen = "4k3/8/8/8/8/2N3N1/8/4K3 w - - 0 1";
+        check(fen, "g3e2", "Nge2");
+        // A pinned rival does not need disambiguation
+        let fen = "4k3/8/8/b7/8/2N3N1/8/4K3 w - - 0 1";
+        check(fen, "g3e2", "Ne2");
+    }
+
+    #[test]
+    fn castling_and_promotion() {
+        let fen = "5k2/8/8/8/8/8/8/4K2R w K - 0 1";
+        check(fen, "O-O", "O-O+");
+        let fen = "r3k3/8/8/8/8/8/8/4K3 b q - 0 1";
+        check(fen, "O-O-O", "O-O-O");
+        let fen = "3qk3/4P3/8/8/8/8/8/4K3 w - - 0 1";
+        check(fen, "e7xd8=Q", "exd8=Q+");
+        check(fen, "e7xd8=N", "exd8=N");
+        let fen = "8/4P1k1/8/8/8/8/8/4K3 w - - 0 1";
+        check(fen, "e7e8=Q", "e8=Q");
+        check(fen, "e7e8=N", "e8=N+");
+    }
+
+    #[test]
+    fn mate() {
+        let fen = "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2";
+        check(fen, "d8h4", "Qh4#");
+        let fen = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+        check(fen, "a1a8", "Ra8#");
+        let fen = "6rk/6pp/7N/8/8/8/8/6K1 w - - 0 1";
+        check(fen, "h6f7", "Nf7#");
+    }
+
+    #[test]
+    fn tolerant() {
+        let b = Board::initial();
+        let nf3 = "g1f3".parse::<Move>().unwrap();
+        for s in &["Nf3!?", "Nf3?", "Ng1f3", "Ng1-f3", " Nf3 ", "N1f3"] {
+            assert_eq!(Move::from_san(s, &b), Ok(nf3), "{}", s);
+        }
+        let b = from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(Move::from_san("0-0", &b), Ok(king_castle(b.active)));
+        assert_eq!(Move::from_san("0-0-0", &b), Ok(queen_castle(b.active)));
+        let b = from_fen("8/4P1k1/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let e8q = "e7e8=Q".parse::<Move>().unwrap();
+        assert_eq!(Move::from_san("e8Q", &b), Ok(e8q));
+        assert_eq!(Move::from_san("e8=Q+", &b), Ok(e8q));
+        assert_eq!(Move::from_san("e8(Q)", &b), Ok(e8q));
+        let b = from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3").unwrap();
+        let exf6 = en_passant(E5, F6);
+        assert_eq!(Move::from_san("exf6 e.p.", &b), Ok(exf6));
+        assert_eq!(Move::from_san("ef6", &b), Ok(exf6));
+    }
+
+    #[test]
+    fn errors() {
+        let b = Board::initial();
+        let err = |s: &str| Move::from_san(s, &b);
+        assert_eq!(err("Nf4"), Err(SanError::Illegal("Nf4".to_string())));
+        assert_eq!(err("O-O"), Err(SanError::Illegal("O-O".to_string())));
+        assert_eq!(err("e5"), Err(SanError::Illegal("e5".to_string())));
+        assert_eq!(err("Xf3"), Err(SanError::Invalid("Xf3".to_string())));
+        assert_eq!(err("Nf9"), Err(SanError::Invalid("Nf9".to_string())));
+        assert_eq!(err(""), Err(SanError::Invalid("".to_string())));
+        assert_eq!(err("hello"), Err(SanError::Invalid("hello".to_string())));
+        let b = from_fen("4k3/8/8/8/R7/8/8/R3K3 w - - 0 1").unwrap();
+        assert_eq!(
+            Move::from_san("Ra3", &b),
+            Err(SanError::Ambiguous("Ra3".to_string()))
+        );
+        let b = from_fen("8/4P1k1/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            Move::from_san("e8", &b),
+            Err(SanError::Illegal("e8".to_string()))
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ];
+        for fen in &fens {
+            let b = from_fen(fen).unwrap();
+            for m in generate_legal(&b) {
+                let san = m.to_san(&b);
+                assert_eq!(Move::from_san(&san, &b), Ok(m), "{} in {}", san, fen);
+            }
+        }
+    }
+}
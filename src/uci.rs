@@ -0,0 +1,199 @@
+use crate::board::Board;
+use crate::field::Field;
+use crate::move_generator::generate_legal;
+use crate::mv::{king_castle, queen_castle, Move};
+use crate::piece::Piece;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum UciError {
+    /// Text which is not a move in UCI notation
+    Invalid(String),
+    /// Move which is not legal in the position
+    Illegal(String),
+}
+
+impl fmt::Display for UciError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UciError::Invalid(s) => write!(f, "Invalid UCI move '{}'", s),
+            UciError::Illegal(s) => write!(f, "Illegal move '{}'", s),
+        }
+    }
+}
+
+impl Error for UciError {}
+
+impl Move {
+    /// Formats the move in UCI long algebraic notation, e.g. `e2e4` or
+    /// `e7e8q`. Castling is written as the king moving two fields, unless
+    /// the king or rook start elsewhere as in Chess960, where the king takes
+    /// its own rook.
+    pub fn to_uci(&self) -> String {
+        let standard = self
+            .castle_side()
+            .is_some_and(|s| *self == king_castle(s) || *self == queen_castle(s));
+        self.format_uci(!standard)
+    }
+
+    /// Formats the move for engines in `UCI_Chess960` mode, which expect
+    /// every castling move as the king taking its own rook
+    pub fn to_uci_chess960(&self) -> String {
+        self.format_uci(true)
+    }
+
+    fn format_uci(&self, king_takes_rook: bool) -> String {
+        match self {
+            Move::KingCastle(c) | Move::QueenCastle(c) if king_takes_rook => {
+                format!("{}{}", c.king_from(), c.rook_from())
+            }
+            Move::KingCastle(c) | Move::QueenCastle(c) => {
+                format!("{}{}", c.king_from(), c.king_to())
+            }
+            _ => {
+                let d = self.move_data().expect("Non-castling move has data");
+                let promotion = match d.promotion() {
+                    Some(p) => p.to_string().to_lowercase(),
+                    None => "".to_string(),
+                };
+                format!("{}{}{}", d.from(), d.to(), promotion)
+            }
+        }
+    }
+
+    /// Parses a move in UCI long algebraic notation. Captures are taken from
+    /// the board. Castling is accepted both as the king moving two fields and
+    /// as the king taking its own rook like in Chess960.
+    pub fn from_uci(s: &str, b: &Board) -> Result<Move, UciError> {
+        let invalid = || UciError::Invalid(s.to_string());
+        if !s.is_ascii() || (s.len() != 4 && s.len() != 5) {
+            return Err(invalid());
+        }
+        let from = s[0..2].parse::<Field>().map_err(|_| invalid())?;
+        let to = s[2..4].parse::<Field>().map_err(|_| invalid())?;
+        let promotion = match &s[4..] {
+            "" => None,
+            "q" => Some(Piece::Queen),
+            "r" => Some(Piece::Rook),
+            "b" => Some(Piece::Bishop),
+            "n" => Some(Piece::Knight),
+            _ => return Err(invalid()),
+        };
+        generate_legal(b)
+            .into_iter()
+            .find(|m| match m {
+                Move::KingCastle(c) | Move::QueenCastle(c) => {
+                    promotion.is_none()
+                        && from == c.king_from()
+                        && (to == c.king_to() || to == c.rook_from())
+                }
+                _ => m.move_data().is_some_and(|d| {
+                    d.from() == from && d.to() == to && d.promotion() == promotion
+                }),
+            })
+            .ok_or_else(|| UciError::Illegal(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::from_fen;
+    use crate::field::named::*;
+    use crate::mv::castle;
+    use crate::piece::Side;
+
+    fn check(fen: &str, m: &str, uci: &str) {
+        let b = from_fen(fen).unwrap();
+        // Move kinds and castling sides follow from the position
+        let m = generate_legal(&b)
+            .into_iter()
+            .find(|g| g.to_string() == m)
+            .unwrap_or_else(|| m.parse::<Move>().unwrap());
+        assert_eq!(m.to_uci(), uci);
+        assert_eq!(Move::from_uci(uci, &b), Ok(m));
+    }
+
+    #[test]
+    fn moves() {
+        let b = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        check(b, "e2e4", "e2e4");
+        check(b, "g1f3", "g1f3");
+        let b = "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2";
+        check(b, "e4xd5", "e4d5");
+        let b = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3";
+        check(b, "e5xf6", "e5f6");
+        let b = "3qk3/4P3/8/8/8/8/8/4K3 w - - 0 1";
+        check(b, "e7xd8=Q", "e7d8q");
+        check(b, "e7xd8=N", "e7d8n");
+        let b = "4k3/8/8/8/8/8/p7/4K3 b - - 0 1";
+        check(b, "a2a1=R", "a2a1r");
+    }
+
+    #[test]
+    fn castling() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        check(fen, "O-O", "e1g1");
+        check(fen, "O-O-O", "e1c1");
+        let b = from_fen(fen).unwrap();
+        assert_eq!(Move::from_uci("e1h1", &b), Ok(king_castle(b.active)));
+        assert_eq!(Move::from_uci("e1a1", &b), Ok(queen_castle(b.active)));
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1";
+        check(fen, "O-O", "e8g8");
+        check(fen, "O-O-O", "e8c8");
+        let b = from_fen(fen).unwrap();
+        assert_eq!(Move::from_uci("e8h8", &b), Ok(king_castle(b.active)));
+        assert_eq!(Move::from_uci("e8a8", &b), Ok(queen_castle(b.active)));
+    }
+
+    #[test]
+    fn chess960() {
+        let white = king_castle(Side::White);
+        assert_eq!(white.to_uci_chess960(), "e1h1");
+        assert_eq!(queen_castle(Side::Black).to_uci_chess960(), "e8a8");
+        assert_eq!(
+            Move::from_uci("e2e4", &Board::initial())
+                .unwrap()
+                .to_uci_chess960(),
+            "e2e4"
+        );
+        // A king on b1 would move to g1 or c1, which is not castling in UCI
+        assert_eq!(castle(B1, F1).to_uci(), "b1f1");
+        assert_eq!(castle(B1, A1).to_uci(), "b1a1");
+        assert_eq!(castle(G8, H8).to_uci_chess960(), "g8h8");
+    }
+
+    #[test]
+    fn errors() {
+        let b = Board::initial();
+        let err = |s: &str| Move::from_uci(s, &b);
+        assert_eq!(err("e2e5"), Err(UciError::Illegal("e2e5".to_string())));
+        assert_eq!(err("e1g1"), Err(UciError::Illegal("e1g1".to_string())));
+        assert_eq!(err("e2e4q"), Err(UciError::Illegal("e2e4q".to_string())));
+        assert_eq!(err("e2e4k"), Err(UciError::Invalid("e2e4k".to_string())));
+        assert_eq!(err("e2e9"), Err(UciError::Invalid("e2e9".to_string())));
+        assert_eq!(err("E2E4"), Err(UciError::Invalid("E2E4".to_string())));
+        assert_eq!(err("0000"), Err(UciError::Invalid("0000".to_string())));
+        assert_eq!(err("e2"), Err(UciError::Invalid("e2".to_string())));
+        assert_eq!(err("é2e4"), Err(UciError::Invalid("é2e4".to_string())));
+    }
+
+    #[test]
+    fn round_trip() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ];
+        for fen in &fens {
+            let b = from_fen(fen).unwrap();
+            for m in generate_legal(&b) {
+                let uci = m.to_uci();
+                assert_eq!(Move::from_uci(&uci, &b), Ok(m), "{} in {}", uci, fen);
+                let uci = m.to_uci_chess960();
+                assert_eq!(Move::from_uci(&uci, &b), Ok(m), "{} in {}", uci, fen);
+            }
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use crate::board::Board;
+use crate::field::{fields, Field};
+use crate::move_generator::in_check;
+use crate::piece::{ColoredPiece, Piece, Side};
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PositionError {
+    /// Side has no king or more than one
+    KingCount(Side, usize),
+    /// Pawn on the first or eighth rank
+    PawnOnBackRank(Field),
+    /// King of the side which is not to move is in check
+    OppositeCheck,
+    /// Castling right without king and rook on their initial fields
+    CastlingRights { side: Side, king_side: bool },
+    /// En passant square not behind a pawn which has just moved two fields
+    EnPassant(Field),
+    /// Side has more than 16 pieces
+    TooManyPieces(Side),
+    /// Side has more than 8 pawns
+    TooManyPawns(Side),
+    /// Side has more promoted pieces than missing pawns
+    TooManyPromotions(Side),
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = |s: &Side| match s {
+            Side::White => "White",
+            Side::Black => "Black",
+        };
+        match self {
+            PositionError::KingCount(s, n) => write!(f, "{} has {} kings", name(s), n),
+            PositionError::PawnOnBackRank(field) => write!(f, "Pawn on back rank at {}", field),
+            PositionError::OppositeCheck => write!(f, "Side not to move is in check"),
+            PositionError::CastlingRights { side, king_side } => write!(
+                f,
+                "{} cannot castle {}",
+                name(side),
+                if *king_side {
+                    "king side"
+                } else {
+                    "queen side"
+                }
+            ),
+            PositionError::EnPassant(field) => write!(f, "Invalid en passant square {}", field),
+            PositionError::TooManyPieces(s) => write!(f, "{} has more than 16 pieces", name(s)),
+            PositionError::TooManyPawns(s) => write!(f, "{} has more than 8 pawns", name(s)),
+            PositionError::TooManyPromotions(s) => {
+                write!(f, "{} has more promoted pieces than missing pawns", name(s))
+            }
+        }
+    }
+}
+
+impl Error for PositionError {}
+
+impl Board {
+    /// Checks that the position can arise in a game
+    pub fn validate(&self) -> Result<(), PositionError> {
+        use crate::field::named::*;
+        for side in &[Side::White, Side::Black] {
+            let side = *side;
+            let count = |piece: Piece| {
+                fields()
+                    .filter(|f| self.pieces[f.0] == ColoredPiece::P(piece, side))
+                    .count()
+            };
+            let kings = count(Piece::King);
+            if kings != 1 {
+                return Err(PositionError::KingCount(side, kings));
+            }
+            let total = fields()
+                .filter(|f| self.pieces[f.0].has_color(side))
+                .count();
+            if total > 16 {
+                return Err(PositionError::TooManyPieces(side));
+            }
+            let pawns = count(Piece::Pawn);
+            if pawns > 8 {
+                return Err(PositionError::TooManyPawns(side));
+            }
+            let extra = |piece: Piece, initial: usize| count(piece).saturating_sub(initial);
+            let promoted = extra(Piece::Queen, 1)
+                + extra(Piece::Rook, 2)
+                + extra(Piece::Bishop, 2)
+                + extra(Piece::Knight, 2);
+            if promoted > 8 - pawns {
+                return Err(PositionError::TooManyPromotions(side));
+            }
+        }
+
+        if let Some(f) = fields().find(|f| {
+            (f.row() == 1 || f.row() == 8)
+                && matches!(self.pieces[f.0], ColoredPiece::P(Piece::Pawn, _))
+        }) {
+            return Err(PositionError::PawnOnBackRank(f));
+        }
+
+        if in_check(self, self.active.opposite()) {
+            return Err(PositionError::OppositeCheck);
+        }
+
+        let rights = [
+            (Side::White, true, E1, H1),
+            (Side::White, false, E1, A1),
+            (Side::Black, true, E8, H8),
+            (Side::Black, false, E8, A8),
+        ];
+        for (idx, (side, king_side, king, rook)) in rights.iter().enumerate() {
+            if self.can_castle[idx]
+                && (self.pieces[king.0] != ColoredPiece::P(Piece::King, *side)
+                    || self.pieces[rook.0] != ColoredPiece::P(Piece::Rook, *side))
+            {
+                return Err(PositionError::CastlingRights {
+                    side: *side,
+                    king_side: *king_side,
+                });
+            }
+        }
+
+        if let Some(f) = self.en_passant {
+            // Row of the en passant square, of the pawn which passed it and
+            // of the field the pawn came from
+            let (row, pawn_row, from_row) = match self.active {
+                Side::White => (6, 5, 7),
+                Side::Black => (3, 4, 2),
+            };
+            let opponent_pawn = ColoredPiece::P(Piece::Pawn, self.active.opposite());
+            if f.row() != row
+                || self.pieces[f.0] != ColoredPiece::Empty
+                || self.pieces[Field::new(pawn_row, f.column()).0] != opponent_pawn
+                || self.pieces[Field::new(from_row, f.column()).0] != ColoredPiece::Empty
+            {
+                return Err(PositionError::EnPassant(f));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::from_fen;
+    use crate::field::named::*;
+
+    fn validate(fen: &str) -> Result<(), PositionError> {
+        from_fen(fen).unwrap().validate()
+    }
+
+    #[test]
+    fn valid() {
+        assert_eq!(Board::initial().validate(), Ok(()));
+        assert_eq!(
+            validate("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3"),
+            Ok(())
+        );
+        assert_eq!(
+            validate("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"),
+            Ok(())
+        );
+        assert_eq!(validate("QQQQQQQk/8/8/8/8/8/8/QQK5 b - - 0 1"), Ok(()));
+    }
+
+    #[test]
+    fn kings() {
+        assert_eq!(
+            validate("8/8/8/8/8/8/8/4K3 w - - 0 1"),
+            Err(PositionError::KingCount(Side::Black, 0))
+        );
+        assert_eq!(
+            validate("4k3/8/8/8/8/8/8/3KK3 w - - 0 1"),
+            Err(PositionError::KingCount(Side::White, 2))
+        );
+        assert_eq!(
+            Board::empty().validate(),
+            Err(PositionError::KingCount(Side::White, 0))
+        );
+    }
+
+    #[test]
+    fn pawns() {
+        assert_eq!(
+            validate("4k3/8/8/8/8/8/8/P3K3 w - - 0 1"),
+            Err(PositionError::PawnOnBackRank(A1))
+        );
+        assert_eq!(
+            validate("4k2p/8/8/8/8/8/8/4K3 w - - 0 1"),
+            Err(PositionError::PawnOnBackRank(H8))
+        );
+    }
+
+    #[test]
+    fn check() {
+        assert_eq!(validate("4k3/8/8/8/8/8/8/4K2R b - - 0 1"), Ok(()));
+        assert_eq!(
+            validate("4k3/8/8/8/8/8/8/4R1K1 w - - 0 1"),
+            Err(PositionError::OppositeCheck)
+        );
+        assert_eq!(
+            validate("8/8/8/3kK3/8/8/8/8 w - - 0 1"),
+            Err(PositionError::OppositeCheck)
+        );
+    }
+
+    #[test]
+    fn castling() {
+        assert_eq!(
+            validate("r3k2r/8/8/8/8/8/8/R3K1R1 w KQkq - 0 1"),
+            Err(PositionError::CastlingRights {
+                side: Side::White,
+                king_side: true
+            })
+        );
+        assert_eq!(validate("r3k2r/8/8/8/8/8/8/R3K1R1 w Qkq - 0 1"), Ok(()));
+        assert_eq!(
+            validate("r4k1r/8/8/8/8/8/8/R3K2R w KQq - 0 1"),
+            Err(PositionError::CastlingRights {
+                side: Side::Black,
+                king_side: false
+            })
+        );
+    }
+
+    #[test]
+    fn en_passant() {
+        assert_eq!(
+            validate("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq e3 0 1"),
+            Err(PositionError::EnPassant(E3))
+        );
+        assert_eq!(
+            validate("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+            Ok(())
+        );
+        assert_eq!(
+            validate("rnbqkbnr/pppppppp/8/8/4P3/4P3/PPP2PPP/RNBQKBNR b KQkq e3 0 1"),
+            Err(PositionError::EnPassant(E3))
+        );
+        assert_eq!(
+            validate("rnbqkbnr/pppp1ppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+            Ok(())
+        );
+        assert_eq!(
+            validate("rnbqkbnr/pppp1ppp/8/8/4p3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+            Err(PositionError::EnPassant(E3))
+        );
+    }
+
+    #[test]
+    fn material() {
+        assert_eq!(
+            validate("4k3/8/8/8/8/NNNNNNNN/PPPPPPPP/4K3 w - - 0 1"),
+            Err(PositionError::TooManyPieces(Side::White))
+        );
+        assert_eq!(
+            validate("4k3/8/8/8/8/P7/PPPPPPPP/4K3 w - - 0 1"),
+            Err(PositionError::TooManyPawns(Side::White))
+        );
+        assert_eq!(
+            validate("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNQ w Qkq - 0 1"),
+            Err(PositionError::TooManyPromotions(Side::White))
+        );
+        assert_eq!(
+            validate("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPP1/RNBQKBNQ w Qkq - 0 1"),
+            Ok(())
+        );
+        assert_eq!(validate("4k3/8/8/8/8/8/8/1NNNK3 w - - 0 1"), Ok(()));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            PositionError::CastlingRights {
+                side: Side::Black,
+                king_side: false
+            }
+            .to_string(),
+            "Black cannot castle queen side"
+        );
+        assert_eq!(
+            PositionError::EnPassant(E3).to_string(),
+            "Invalid en passant square e3"
+        );
+    }
+}
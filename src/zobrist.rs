@@ -0,0 +1,181 @@
+use crate::board::Board;
+use crate::field::{Field, COUNT};
+use crate::move_generator::in_check;
+use crate::piece::{ColoredPiece, Piece, Side};
+
+/// Random keys for every piece on every field, the side to move, each
+/// castling right and each en-passant file
+struct Keys {
+    pieces: [[u64; COUNT]; 12],
+    black: u64,
+    castling: [u64; 4],
+    en_passant: [u64; 8],
+}
+
+/// Number `n` of the splitmix64 sequence, so the keys are fixed at compile
+/// time and stable between runs
+const fn random(n: u64) -> u64 {
+    let mut z = 0x5eed_u64.wrapping_add((n + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+const fn keys() -> Keys {
+    let mut n = 0;
+    let mut rv = Keys {
+        pieces: [[0; COUNT]; 12],
+        black: 0,
+        castling: [0; 4],
+        en_passant: [0; 8],
+    };
+    let mut i = 0;
+    while i < 12 {
+        let mut f = 0;
+        while f < COUNT {
+            rv.pieces[i][f] = random(n);
+            n += 1;
+            f += 1;
+        }
+        i += 1;
+    }
+    rv.black = random(n);
+    n += 1;
+    let mut i = 0;
+    while i < 4 {
+        rv.castling[i] = random(n);
+        n += 1;
+        i += 1;
+    }
+    let mut i = 0;
+    while i < 8 {
+        rv.en_passant[i] = random(n);
+        n += 1;
+        i += 1;
+    }
+    rv
+}
+
+const KEYS: Keys = keys();
+
+/// Key of a piece standing on field `f`, zero for an empty field
+pub(crate) fn piece_key(piece: ColoredPiece, f: Field) -> u64 {
+    match piece {
+        ColoredPiece::Empty => 0,
+        ColoredPiece::P(p, s) => {
+            let kind = match p {
+                Piece::King => 0,
+                Piece::Queen => 1,
+                Piece::Rook => 2,
+                Piece::Bishop => 3,
+                Piece::Knight => 4,
+                Piece::Pawn => 5,
+            };
+            KEYS.pieces[kind * 2 + s as usize][f.0]
+        }
+    }
+}
+
+/// Key of the side to move, castling rights and en-passant field. The
+/// en-passant file only counts when the side to move can legally capture
+/// there, so positions which only differ by an unusable field are equal.
+pub(crate) fn state_key(b: &Board) -> u64 {
+    let mut rv = 0;
+    if b.active == Side::Black {
+        rv ^= KEYS.black;
+    }
+    for (idx, can_castle) in b.can_castle.iter().enumerate() {
+        if *can_castle {
+            rv ^= KEYS.castling[idx];
+        }
+    }
+    if let Some(f) = legal_en_passant(b) {
+        rv ^= KEYS.en_passant[f.column() - 1];
+    }
+    rv
+}
+
+/// Fields of pawns of the side to move next to the pawn which has just
+/// advanced two fields
+fn en_passant_pawns(b: &Board, f: Field) -> impl Iterator<Item = Field> + '_ {
+    let row = match b.active {
+        Side::White => 5,
+        Side::Black => 4,
+    };
+    let pawn = ColoredPiece::P(Piece::Pawn, b.active);
+    (f.column() - 1..=f.column() + 1)
+        .step_by(2)
+        .filter(|c| (1..=8).contains(c))
+        .map(move |c| Field::new(row, c))
+        .filter(move |from| b.pieces[from.0] == pawn)
+}
+
+pub(crate) fn en_passant_possible(b: &Board, f: Field) -> bool {
+    en_passant_pawns(b, f).next().is_some()
+}
+
+/// En-passant field of the position if the side to move can legally capture
+/// there
+pub(crate) fn legal_en_passant(b: &Board) -> Option<Field> {
+    let f = b.en_passant?;
+    let legal = en_passant_pawns(b, f).any(|from| {
+        // Only the fields are changed, the key of `after` is not used
+        let mut after = b.clone();
+        after.pieces[from.0] = ColoredPiece::Empty;
+        after.pieces[Field::new(from.row(), f.column()).0] = ColoredPiece::Empty;
+        after.pieces[f.0] = ColoredPiece::P(Piece::Pawn, b.active);
+        !in_check(&after, b.active)
+    });
+    if legal {
+        Some(f)
+    } else {
+        None
+    }
+}
+
+/// Computes the key of a position from scratch
+pub(crate) fn compute(b: &Board) -> u64 {
+    b.pieces
+        .iter()
+        .enumerate()
+        .fold(state_key(b), |rv, (idx, piece)| {
+            rv ^ piece_key(*piece, Field(idx))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::from_fen;
+
+    #[test]
+    fn distinct_keys() {
+        let mut all: Vec<u64> = KEYS.pieces.iter().flatten().cloned().collect();
+        all.push(KEYS.black);
+        all.extend_from_slice(&KEYS.castling);
+        all.extend_from_slice(&KEYS.en_passant);
+        let count = all.len();
+        all.sort_unstable();
+        all.dedup();
+        assert_eq!(all.len(), count);
+        assert!(all.iter().all(|k| *k != 0));
+    }
+
+    #[test]
+    fn en_passant() {
+        // No black pawn can take on e3, so the field does not count
+        let with = from_fen("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1").unwrap();
+        let without = from_fen("4k3/8/8/8/4P3/8/8/4K3 b - - 0 1").unwrap();
+        assert_eq!(compute(&with), compute(&without));
+        let with = from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").unwrap();
+        let without = from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1").unwrap();
+        assert_ne!(compute(&with), compute(&without));
+        let with = from_fen("4k3/8/8/pP6/8/8/8/4K3 w - a6 0 1").unwrap();
+        let without = from_fen("4k3/8/8/pP6/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_ne!(compute(&with), compute(&without));
+        // Taking on c6 would expose the king to the rook
+        let with = from_fen("4k3/8/8/KPp4r/8/8/8/8 w - c6 0 1").unwrap();
+        let without = from_fen("4k3/8/8/KPp4r/8/8/8/8 w - - 0 1").unwrap();
+        assert_eq!(compute(&with), compute(&without));
+    }
+}
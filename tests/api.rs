@@ -0,0 +1,56 @@
+use chess::fields::*;
+use chess::pieces;
+use chess::{
+    divide, from_fen, generate, generate_legal, perft, to_fen, Board, ColoredPiece, Field, Move,
+    Piece, Side,
+};
+
+#[test]
+fn fen_round_trip() {
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    let b = from_fen(fen).unwrap();
+    assert_eq!(to_fen(&b), fen);
+    assert_eq!(b.pieces[E1.0], pieces::K);
+    assert_eq!(b.pieces[A6.0], ColoredPiece::P(Piece::Bishop, Side::Black));
+    assert!(from_fen("not a fen").is_err());
+}
+
+#[test]
+fn parse_and_display() {
+    let fen = "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2";
+    let b = fen.parse::<Board>().unwrap();
+    assert_eq!(b.en_passant, Some(D6));
+    assert_eq!(b.to_string(), fen);
+    assert_eq!(format!("{}", Board::initial()), to_fen(&Board::initial()));
+}
+
+#[test]
+fn play_moves() {
+    let mut b = Board::initial();
+    let moves = generate_legal(&b);
+    assert_eq!(moves.len(), 20);
+    let e2e4 = Move::from_uci("e2e4", &b).unwrap();
+    assert!(moves.contains(&e2e4));
+    assert!(e2e4.is_double_push());
+    let undo = b.make_move(e2e4);
+    assert_eq!(b.active, Side::Black);
+    assert_eq!(b.en_passant, Some("e3".parse::<Field>().unwrap()));
+    b.unmake_move(e2e4, undo);
+    assert_eq!(b, Board::initial());
+}
+
+#[test]
+fn legal_and_pseudo_legal() {
+    let b = from_fen("4k3/8/8/8/8/8/4r3/4K3 w - - 0 1").unwrap();
+    assert_eq!(generate(&b).len(), 5);
+    assert_eq!(generate_legal(&b).len(), 3);
+}
+
+#[test]
+fn counting() {
+    let b = Board::initial();
+    assert_eq!(perft(&b, 3), 8902);
+    let split = divide(&b, 2);
+    assert_eq!(split.len(), 20);
+    assert!(split.iter().all(|(_, nodes)| *nodes == 20));
+}